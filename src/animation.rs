@@ -1,22 +1,22 @@
 use std::fmt;
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::pin::Pin;
 use std::sync::{Arc, Weak, Mutex};
+use std::future::Future;
 use std::task::{Poll, Waker, Context};
 
-use futures_util::future::{ready, FutureExt};
-use futures_signals::CancelableFutureHandle;
-use futures_signals::signal::{Signal, SignalExt, WaitFor, MutableSignal, Mutable};
+use futures_util::future::FutureExt;
+use futures_signals::signal::{Signal, SignalExt, WaitFor, MutableSignal, Mutable, ReadOnlyMutable, always};
 use futures_signals::signal_vec::{SignalVec, VecDiff};
-use discard::DiscardOnDrop;
 use pin_project::pin_project;
 use wasm_bindgen::{JsCast, UnwrapThrowExt};
 use wasm_bindgen::closure::Closure;
-use web_sys::window;
+use web_sys::{window, HtmlElement, Node};
 
-use crate::operations::spawn_future;
-use crate::utils::UnwrapJsExt;
+use crate::bindings;
+use crate::dom::EventOptions;
+use crate::utils::{EventListener, MutableListener, UnwrapJsExt};
 
 
 struct RafState {
@@ -77,18 +77,160 @@ impl Drop for Raf {
 }
 
 
+/// The source of time for [`timestamps`], see [`set_clock`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Clock {
+    /// The time is updated every frame by `requestAnimationFrame`, this is the default.
+    #[default]
+    AnimationFrame,
+    /// The time only changes when [`advance_clock`] is called.
+    ///
+    /// This makes animations deterministic, which is useful for tests.
+    Manual,
+}
+
+
+struct TimestampsCallback {
+    // The callback is removed when the OnTimestampDiff is dropped
+    alive: Weak<()>,
+    callback: Box<dyn FnMut(f64)>,
+}
+
 struct TimestampsManager {
     raf: Option<Raf>,
+    clock: Clock,
+    paused: bool,
+    // The last time which was sent to the signals and callbacks
+    time: Option<f64>,
+    // Subtracted from the requestAnimationFrame time, so the time doesn't include the time when it was paused
+    offset: f64,
+    previous_frame: Option<f64>,
     // TODO make this more efficient
     states: Vec<Weak<Mutex<TimestampsState>>>,
+    callbacks: Vec<TimestampsCallback>,
 }
 
 impl TimestampsManager {
     fn new() -> Self {
         Self {
             raf: None,
+            clock: Clock::AnimationFrame,
+            paused: false,
+            time: None,
+            offset: 0.0,
+            previous_frame: None,
             states: vec![],
+            callbacks: vec![],
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.states.is_empty() && self.callbacks.is_empty()
+    }
+
+    fn start_raf(&mut self, timestamps_manager: &Rc<RefCell<TimestampsManager>>) {
+        if self.clock == Clock::AnimationFrame && self.raf.is_none() {
+            let timestamps_manager = timestamps_manager.clone();
+
+            self.raf = Some(Raf::new(move |frame| {
+                let time = {
+                    let mut lock = timestamps_manager.borrow_mut();
+
+                    let previous_frame = lock.previous_frame.replace(frame);
+
+                    if lock.paused {
+                        lock.offset += previous_frame.map(|previous| frame - previous).unwrap_or(0.0);
+
+                        // tick isn't called while paused, so the dropped animations are removed here instead
+                        lock.states.retain(|state| state.strong_count() > 0);
+                        lock.callbacks.retain(|callback| callback.alive.strong_count() > 0);
+
+                        if lock.is_empty() {
+                            lock.stop_raf();
+                        }
+
+                        None
+
+                    } else {
+                        let mut time = frame - lock.offset;
+
+                        // The time must never go backwards, e.g. after switching from the Manual clock
+                        if let Some(previous) = lock.time {
+                            if time < previous {
+                                lock.offset = frame - previous;
+                                time = previous;
+                            }
+                        }
+
+                        Some(time)
+                    }
+                };
+
+                if let Some(time) = time {
+                    tick(&timestamps_manager, time);
+                }
+            }));
+        }
+    }
+
+    fn stop_raf(&mut self) {
+        self.raf = None;
+        self.previous_frame = None;
+    }
+}
+
+
+// Sends the time to all of the signals and callbacks
+fn tick(timestamps_manager: &Rc<RefCell<TimestampsManager>>, time: f64) {
+    let mut callbacks = {
+        let mut lock = timestamps_manager.borrow_mut();
+
+        lock.time = Some(time);
+
+        lock.states.retain(|state| {
+            if let Some(state) = state.upgrade() {
+                let mut lock = state.lock().unwrap_throw();
+
+                lock.changed = true;
+                lock.value = Some(time);
+
+                if let Some(waker) = lock.waker.take() {
+                    drop(lock);
+                    waker.wake();
+                }
+
+                true
+
+            } else {
+                false
+            }
+        });
+
+        // The callbacks are taken out so that they can create new timestamps
+        std::mem::take(&mut lock.callbacks)
+    };
+
+    callbacks.retain_mut(|callback| {
+        if callback.alive.strong_count() > 0 {
+            (callback.callback)(time);
+            callback.alive.strong_count() > 0
+
+        } else {
+            false
         }
+    });
+
+    let mut lock = timestamps_manager.borrow_mut();
+
+    // Callbacks which were added while running the callbacks are put after the existing callbacks
+    callbacks.append(&mut lock.callbacks);
+    lock.callbacks = callbacks;
+
+    if lock.is_empty() {
+        lock.stop_raf();
+        // TODO is this a good idea ?
+        lock.states = vec![];
+        lock.callbacks = vec![];
     }
 }
 
@@ -157,44 +299,218 @@ pub fn timestamps() -> Timestamps {
 
             lock.states.push(Arc::downgrade(&timestamps.state));
 
-            if let None = lock.raf {
-                let timestamps_manager = timestamps_manager.clone();
-
-                lock.raf = Some(Raf::new(move |time| {
-                    let mut lock = timestamps_manager.borrow_mut();
-
-                    lock.states.retain(|state| {
-                        if let Some(state) = state.upgrade() {
-                            let mut lock = state.lock().unwrap_throw();
+            lock.start_raf(timestamps_manager);
+        }
 
-                            lock.changed = true;
-                            lock.value = Some(time);
+        timestamps
+    })
+}
 
-                            if let Some(waker) = lock.waker.take() {
-                                drop(lock);
-                                waker.wake();
-                            }
 
-                            true
+/// Changes the source of time for [`timestamps`], which is used by all of the animations.
+///
+/// When switching clocks the time continues from the current time, it never goes backwards.
+///
+/// # Example
+///
+/// ```rust
+/// set_clock(Clock::Manual);
+///
+/// let animation = MutableAnimation::new(1000.0);
+/// animation.animate_to(Percentage::END);
+///
+/// advance_clock(250.0);
+/// assert_eq!(animation.current_percentage(), Percentage::new(0.25));
+/// ```
+pub fn set_clock(clock: Clock) {
+    TIMESTAMPS_MANAGER.with(|timestamps_manager| {
+        let mut lock = timestamps_manager.borrow_mut();
 
-                        } else {
-                            false
-                        }
-                    });
+        if lock.clock != clock {
+            lock.clock = clock;
 
-                    if lock.states.len() == 0 {
-                        lock.raf = None;
-                        // TODO is this a good idea ?
-                        lock.states = vec![];
+            match clock {
+                Clock::AnimationFrame => {
+                    if !lock.is_empty() {
+                        lock.start_raf(timestamps_manager);
                     }
-                }));
+                },
+                Clock::Manual => {
+                    lock.stop_raf();
+                    lock.time.get_or_insert(0.0);
+                },
             }
         }
+    })
+}
 
-        timestamps
+#[inline]
+pub fn clock() -> Clock {
+    TIMESTAMPS_MANAGER.with(|timestamps_manager| timestamps_manager.borrow().clock)
+}
+
+/// Moves the [`Clock::Manual`] clock forward by `ms` milliseconds, and then immediately updates the animations.
+///
+/// This works even if the clock is paused, so it can be used to step through the animations frame by frame.
+///
+/// # Panics
+///
+/// It panics if the clock isn't [`Clock::Manual`].
+#[track_caller]
+pub fn advance_clock(ms: f64) {
+    debug_assert!(ms >= 0.0);
+
+    TIMESTAMPS_MANAGER.with(|timestamps_manager| {
+        let time = {
+            let lock = timestamps_manager.borrow();
+
+            assert!(lock.clock == Clock::Manual, "advance_clock can only be used with Clock::Manual");
+
+            lock.time.unwrap_or(0.0) + ms
+        };
+
+        tick(timestamps_manager, time);
+    })
+}
+
+/// Pauses the time for all of the animations, e.g. so that a screenshot can be taken.
+///
+/// When it is resumed, the animations continue from where they were paused.
+pub fn pause_clock() {
+    TIMESTAMPS_MANAGER.with(|timestamps_manager| {
+        timestamps_manager.borrow_mut().paused = true;
+    })
+}
+
+pub fn resume_clock() {
+    TIMESTAMPS_MANAGER.with(|timestamps_manager| {
+        timestamps_manager.borrow_mut().paused = false;
+    })
+}
+
+#[inline]
+pub fn is_clock_paused() -> bool {
+    TIMESTAMPS_MANAGER.with(|timestamps_manager| timestamps_manager.borrow().paused)
+}
+
+
+/// What animations do when the user has enabled reduced motion in their operating system.
+///
+/// It applies to [`MutableAnimation`] (including [`animated_map`](AnimatedSignalVec::animated_map) and
+/// [`child_signal_animated`](crate::DomBuilder::child_signal_animated)), [`SpringAnimation`], [`Timeline`],
+/// [`children_signal_vec_flip`](crate::DomBuilder::children_signal_vec_flip), and [Web Animations](crate::web_animation).
+///
+/// The global policy is set with [`set_reduced_motion_policy`], and it can be overridden for
+/// individual animations with [`MutableAnimation::set_reduced_motion`], [`SpringAnimation::set_reduced_motion`],
+/// [`Timeline::set_reduced_motion`], [`children_signal_vec_flip_reduced_motion`](crate::DomBuilder::children_signal_vec_flip_reduced_motion),
+/// and [`AnimationOptions::reduced_motion`](crate::web_animation::AnimationOptions::reduced_motion).
+///
+/// The user's setting is read from the operating system, it can be overridden with [`set_prefers_reduced_motion`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ReducedMotion {
+    /// Animations jump directly to their end state, this is the default.
+    #[default]
+    Instant,
+    /// The duration of animations is multiplied by the factor, e.g. `Scale(0.25)` plays them 4 times faster.
+    Scale(f64),
+    /// Animations play normally.
+    Ignore,
+}
+
+impl ReducedMotion {
+    // Returns the multiplier for the duration of animations
+    fn scale(self) -> f64 {
+        match self {
+            ReducedMotion::Ignore => 1.0,
+            _ if !is_reduced_motion() => 1.0,
+            ReducedMotion::Instant => 0.0,
+            ReducedMotion::Scale(scale) => scale,
+        }
+    }
+}
+
+thread_local! {
+    static REDUCED_MOTION_POLICY: Cell<ReducedMotion> = const { Cell::new(ReducedMotion::Instant) };
+    static PREFERS_REDUCED_MOTION: RefCell<Option<MutableListener<bool>>> = const { RefCell::new(None) };
+    static PREFERS_REDUCED_MOTION_OVERRIDE: Mutable<Option<bool>> = Mutable::new(None);
+}
+
+fn prefers_reduced_motion_mutable() -> ReadOnlyMutable<bool> {
+    PREFERS_REDUCED_MOTION.with(|prefers| {
+        let mut prefers = prefers.borrow_mut();
+
+        // The listener is never removed, because it is used by every animation
+        let prefers = prefers.get_or_insert_with(|| {
+            let query = bindings::match_media("(prefers-reduced-motion: reduce)");
+
+            let mutable = Mutable::new(query.matches());
+
+            let listener = {
+                let mutable = mutable.clone();
+                let target = query.clone();
+
+                EventListener::new(&target, "change", &EventOptions::default(), move |_| {
+                    mutable.set_neq(query.matches());
+                })
+            };
+
+            MutableListener::new(mutable, listener)
+        });
+
+        prefers.as_mutable().read_only()
+    })
+}
+
+#[inline]
+fn is_reduced_motion() -> bool {
+    PREFERS_REDUCED_MOTION_OVERRIDE.with(|value| value.get())
+        .unwrap_or_else(|| prefers_reduced_motion_mutable().get())
+}
+
+/// `Signal` which is `true` if the user has enabled reduced motion in their operating system.
+///
+/// It updates automatically when the user changes the setting, or when it is overridden with [`set_prefers_reduced_motion`].
+pub fn prefers_reduced_motion() -> impl Signal<Item = bool> {
+    PREFERS_REDUCED_MOTION_OVERRIDE.with(|value| value.signal()).switch(|value| -> Box<dyn Signal<Item = bool> + Unpin> {
+        match value {
+            Some(value) => Box::new(always(value)),
+            None => Box::new(prefers_reduced_motion_mutable().signal()),
+        }
     })
 }
 
+/// Overrides the user's reduced motion setting, `None` uses the operating system's setting, this is the default.
+///
+/// This is useful for a reduced motion setting inside of the app, and for tests which run
+/// outside of the browser (where the operating system's setting can't be read).
+#[inline]
+pub fn set_prefers_reduced_motion(value: Option<bool>) {
+    PREFERS_REDUCED_MOTION_OVERRIDE.with(|mutable| mutable.set_neq(value));
+}
+
+/// Sets the [`ReducedMotion`] policy which is used by all animations.
+///
+/// It applies when an animation starts, animations which are already playing are not changed.
+#[inline]
+pub fn set_reduced_motion_policy(policy: ReducedMotion) {
+    if let ReducedMotion::Scale(scale) = policy {
+        debug_assert!(scale >= 0.0);
+    }
+
+    REDUCED_MOTION_POLICY.with(|global| global.set(policy));
+}
+
+#[inline]
+pub fn reduced_motion_policy() -> ReducedMotion {
+    REDUCED_MOTION_POLICY.with(|global| global.get())
+}
+
+// Uses the animation's policy if it has one, otherwise it uses the global policy
+#[inline]
+pub(crate) fn reduced_motion_scale(policy: Option<ReducedMotion>) -> f64 {
+    policy.unwrap_or_else(reduced_motion_policy).scale()
+}
+
 
 pub trait AnimatedSignalVec: SignalVec {
     type Animation;
@@ -224,6 +540,11 @@ impl<S: SignalVec> AnimatedSignalVec for S {
 pub struct AnimatedMapBroadcaster(MutableAnimation);
 
 impl AnimatedMapBroadcaster {
+    #[inline]
+    pub(crate) fn new(animation: MutableAnimation) -> Self {
+        AnimatedMapBroadcaster(animation)
+    }
+
     // TODO it should return a custom type
     #[inline]
     pub fn signal(&self) -> MutableAnimationSignal {
@@ -232,6 +553,16 @@ impl AnimatedMapBroadcaster {
 }
 
 
+/// How the old and new children are animated by [`DomBuilder::child_signal_animated`](crate::DomBuilder::child_signal_animated).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionMode {
+    /// The new child is inserted after the old child has finished its exit animation.
+    Sequential,
+    /// The new child plays its enter animation at the same time as the old child plays its exit animation.
+    CrossFade,
+}
+
+
 #[derive(Debug)]
 struct AnimatedMapState {
     animation: MutableAnimation,
@@ -504,6 +835,12 @@ impl Percentage {
         self.0
     }
 
+    /// Interpolates between `from` and `to`, see [`Interpolate`].
+    #[inline]
+    pub fn interpolate<A>(self, from: &A, to: &A) -> A where A: Interpolate {
+        from.interpolate(to, self)
+    }
+
     pub fn none_if(self, percentage: f64) -> Option<Self> {
         if self.0 == percentage {
             None
@@ -520,6 +857,100 @@ fn range_inclusive(percentage: f64, low: f64, high: f64) -> f64 {
 }
 
 
+/// Values which can be animated by interpolating between a start and end value.
+///
+/// # Example
+///
+/// ```rust
+/// let from = Rgb(255, 0, 0);
+/// let to = Rgb(0, 0, 255);
+///
+/// html!("div", {
+///     .style_signal("color", animation.signal().map(move |t| t.interpolate(&from, &to)))
+/// })
+/// ```
+pub trait Interpolate {
+    /// Returns the value which is `percentage` of the way from `self` to `other`.
+    ///
+    /// The percentage can be below `0.0` or above `1.0` (e.g. with [`easing::back`]).
+    fn interpolate(&self, other: &Self, percentage: Percentage) -> Self;
+}
+
+impl Interpolate for f64 {
+    #[inline]
+    fn interpolate(&self, other: &Self, percentage: Percentage) -> Self {
+        range_inclusive(percentage.0, *self, *other)
+    }
+}
+
+impl Interpolate for f32 {
+    #[inline]
+    fn interpolate(&self, other: &Self, percentage: Percentage) -> Self {
+        range_inclusive(percentage.0, *self as f64, *other as f64) as f32
+    }
+}
+
+impl Interpolate for Percentage {
+    #[inline]
+    fn interpolate(&self, other: &Self, percentage: Percentage) -> Self {
+        Percentage::new_unchecked(self.0.interpolate(&other.0, percentage))
+    }
+}
+
+impl<A, const N: usize> Interpolate for [A; N] where A: Interpolate {
+    #[inline]
+    fn interpolate(&self, other: &Self, percentage: Percentage) -> Self {
+        std::array::from_fn(|index| self[index].interpolate(&other[index], percentage))
+    }
+}
+
+impl Interpolate for () {
+    #[inline]
+    fn interpolate(&self, _other: &Self, _percentage: Percentage) -> Self {}
+}
+
+macro_rules! interpolate_tuple {
+    ($($name:ident $index:tt),*) => {
+        impl<$($name),*> Interpolate for ($($name,)*) where $($name: Interpolate),* {
+            #[inline]
+            fn interpolate(&self, other: &Self, percentage: Percentage) -> Self {
+                ($(self.$index.interpolate(&other.$index, percentage),)*)
+            }
+        }
+    };
+}
+
+interpolate_tuple!(A 0);
+interpolate_tuple!(A 0, B 1);
+interpolate_tuple!(A 0, B 1, C 2);
+interpolate_tuple!(A 0, B 1, C 2, D 3);
+
+
+/// A 2D point which can be interpolated.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    #[inline]
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+}
+
+impl Interpolate for Point {
+    #[inline]
+    fn interpolate(&self, other: &Self, percentage: Percentage) -> Self {
+        Self {
+            x: self.x.interpolate(&other.x, percentage),
+            y: self.y.interpolate(&other.y, percentage),
+        }
+    }
+}
+
+
 /*pub struct MutableTimestamps<F> {
     callback: Arc<F>,
     animating: Mutex<Option<DiscardOnDrop<CancelableFutureHandle>>>,
@@ -579,20 +1010,37 @@ pub fn timestamps_difference() -> impl Signal<Item = Option<f64>> {
 }
 
 
-pub struct OnTimestampDiff(DiscardOnDrop<CancelableFutureHandle>);
+/// Calls the callback every frame with the number of milliseconds since it was created, until it is dropped.
+///
+/// With [`Clock::AnimationFrame`] the callback is called from `requestAnimationFrame`, and with [`Clock::Manual`]
+/// it is called synchronously by [`advance_clock`]. Changing the clock with [`set_clock`] doesn't call it.
+pub struct OnTimestampDiff(Arc<()>);
 
 impl OnTimestampDiff {
     pub fn new<F>(mut callback: F) -> Self where F: FnMut(f64) + 'static {
-        OnTimestampDiff(spawn_future(
-            timestamps_absolute_difference()
-                .for_each(move |diff| {
-                    if let Some(diff) = diff {
-                        callback(diff);
-                    }
+        let alive = Arc::new(());
 
-                    ready(())
-                })
-        ))
+        TIMESTAMPS_MANAGER.with(|timestamps_manager| {
+            let mut lock = timestamps_manager.borrow_mut();
+
+            // The Manual clock starts at the current time, so that `advance_clock` is exact
+            let mut starting_time = match lock.clock {
+                Clock::Manual => lock.time,
+                Clock::AnimationFrame => None,
+            };
+
+            lock.callbacks.push(TimestampsCallback {
+                alive: Arc::downgrade(&alive),
+                callback: Box::new(move |current_time| {
+                    let starting_time = *starting_time.get_or_insert(current_time);
+                    callback(current_time - starting_time);
+                }),
+            });
+
+            lock.start_raf(timestamps_manager);
+        });
+
+        OnTimestampDiff(alive)
     }
 }
 
@@ -604,6 +1052,127 @@ impl fmt::Debug for OnTimestampDiff {
 }
 
 
+struct FlipAnimation {
+    element: HtmlElement,
+    _animating: OnTimestampDiff,
+}
+
+impl FlipAnimation {
+    fn stop(self) {
+        bindings::remove_style(&self.element.style(), "transform");
+    }
+}
+
+// Used by `DomBuilder::children_signal_vec_flip` to animate elements when they move,
+// see https://aerotwist.com/blog/flip-your-animations/
+pub(crate) struct Flip {
+    duration: f64,
+    reduced_motion: Option<ReducedMotion>,
+    // Finished animations remove themselves, so this only contains the animations which are playing
+    animations: Rc<RefCell<Vec<FlipAnimation>>>,
+}
+
+impl Flip {
+    pub(crate) fn new(duration: f64, reduced_motion: Option<ReducedMotion>) -> Self {
+        debug_assert!(duration >= 0.0);
+
+        Self {
+            duration,
+            reduced_motion,
+            animations: Rc::new(RefCell::new(vec![])),
+        }
+    }
+
+    /// Measures the positions of the elements before they are moved.
+    ///
+    /// This measures the current visual position, so interrupted animations continue smoothly.
+    pub(crate) fn first<'a, I>(&mut self, nodes: I) -> Vec<(HtmlElement, f64, f64)> where I: IntoIterator<Item = &'a Node> {
+        let first = nodes.into_iter()
+            .filter_map(|node| node.dyn_ref::<HtmlElement>())
+            .map(|element| {
+                let rect = element.get_bounding_client_rect();
+                (element.clone(), rect.left(), rect.top())
+            })
+            .collect();
+
+        self.stop();
+
+        first
+    }
+
+    /// Measures the positions of the elements after they are moved, and then animates them
+    /// from their old position to their new position.
+    pub(crate) fn play<'a, I>(&mut self, first: Vec<(HtmlElement, f64, f64)>, nodes: I) where I: IntoIterator<Item = &'a Node> {
+        let duration = self.duration * reduced_motion_scale(self.reduced_motion);
+
+        if duration <= 0.0 {
+            return;
+        }
+
+        for node in nodes {
+            if let Some(element) = node.dyn_ref::<HtmlElement>() {
+                if let Some((_, left, top)) = first.iter().find(|(old, _, _)| old == element) {
+                    let rect = element.get_bounding_client_rect();
+
+                    let x = left - rect.left();
+                    let y = top - rect.top();
+
+                    if x != 0.0 || y != 0.0 {
+                        let style = element.style();
+
+                        let set_offset = move |percentage: f64| {
+                            let value = format!("translate({}px, {}px)", x * percentage, y * percentage);
+                            bindings::set_style(&style, "transform", &value, false);
+                        };
+
+                        // Invert, so that the element is visually in its old position
+                        set_offset(1.0);
+
+                        let animating = {
+                            let animations = Rc::downgrade(&self.animations);
+                            let element = element.clone();
+
+                            OnTimestampDiff::new(move |diff| {
+                                let percentage = diff / duration;
+
+                                if percentage >= 1.0 {
+                                    if let Some(animations) = animations.upgrade() {
+                                        let mut animations = animations.borrow_mut();
+
+                                        // This also stops the OnTimestampDiff
+                                        if let Some(index) = animations.iter().position(|animation| animation.element == element) {
+                                            animations.swap_remove(index).stop();
+                                        }
+                                    }
+
+                                } else {
+                                    let eased = easing::out(Percentage::new_unchecked(percentage), easing::cubic);
+                                    set_offset(eased.invert().into_f64());
+                                }
+                            })
+                        };
+
+                        self.animations.borrow_mut().push(FlipAnimation {
+                            element: element.clone(),
+                            _animating: animating,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn stop(&mut self) {
+        // The animations are taken out so that the RefCell isn't borrowed while the styles are removed
+        let animations = std::mem::take(&mut *self.animations.borrow_mut());
+
+        for animation in animations {
+            animation.stop();
+        }
+    }
+}
+
+
 #[derive(Debug)]
 pub struct MutableAnimationSignal(MutableSignal<Percentage>);
 
@@ -617,36 +1186,143 @@ impl Signal for MutableAnimationSignal {
 }
 
 
-// TODO verify that this is Sync and Send
-struct MutableAnimationState {
-    playing: bool,
-    duration: f64,
-    end: Percentage,
-    _animating: Option<OnTimestampDiff>,
+/// How many times a [`MutableAnimation`] plays, see [`MutableAnimation::set_repeat`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Repeat {
+    /// Plays once, this is the default.
+    #[default]
+    Once,
+    /// Plays a total of `n` times.
+    Times(u32),
+    /// Plays until it is interrupted.
+    Forever,
 }
 
-struct MutableAnimationInner {
-    state: Mutex<MutableAnimationState>,
-    value: Mutable<Percentage>,
+
+#[derive(Debug)]
+struct CompletionState {
+    result: Option<bool>,
+    wakers: Vec<Waker>,
 }
 
-// TODO deref to ReadOnlyMutable ?
-// TODO provide read_only() method ?
-// TODO add `is_playing` method ?
-#[derive(Clone)]
-pub struct MutableAnimation {
-    inner: Arc<MutableAnimationInner>,
+#[derive(Debug, Clone)]
+struct Completion {
+    state: Arc<Mutex<CompletionState>>,
 }
 
-impl fmt::Debug for MutableAnimation {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let state = self.inner.state.lock().unwrap_throw();
+impl Completion {
+    fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(CompletionState {
+                result: None,
+                wakers: vec![],
+            })),
+        }
+    }
+
+    fn done(result: bool) -> Self {
+        let completion = Self::new();
+        completion.resolve(result);
+        completion
+    }
+
+    fn is_success(&self) -> bool {
+        self.state.lock().unwrap_throw().result == Some(true)
+    }
+
+    fn resolve(&self, result: bool) {
+        let wakers = {
+            let mut lock = self.state.lock().unwrap_throw();
+
+            if lock.result.is_some() {
+                return;
+            }
+
+            lock.result = Some(result);
+            std::mem::take(&mut lock.wakers)
+        };
+
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+
+/// A future which is returned by [`MutableAnimation::animate_to`].
+///
+/// It resolves to `true` when the animation reaches its target, or `false` if the animation
+/// was interrupted (e.g. by calling `animate_to` with a different target, or `jump_to`).
+///
+/// The animation plays even if the future isn't polled.
+#[derive(Debug)]
+pub struct AnimateTo {
+    completion: Completion,
+}
+
+impl Future for AnimateTo {
+    type Output = bool;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut lock = self.completion.state.lock().unwrap_throw();
+
+        match lock.result {
+            Some(result) => Poll::Ready(result),
+            None => {
+                // The future can be polled many times before it resolves, so the same waker is only stored once
+                if !lock.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    lock.wakers.push(cx.waker().clone());
+                }
+
+                Poll::Pending
+            },
+        }
+    }
+}
+
+
+// TODO verify that this is Sync and Send
+struct MutableAnimationState {
+    playing: bool,
+    duration: f64,
+    // Where the current cycle started, used for repeating
+    start: Percentage,
+    end: Percentage,
+    repeat: Repeat,
+    yoyo: bool,
+    // How many times the current target still needs to play
+    remaining: Option<u32>,
+    // Overrides the global ReducedMotion policy
+    reduced_motion: Option<ReducedMotion>,
+    completion: Completion,
+    is_animating: Mutable<bool>,
+    _animating: Option<OnTimestampDiff>,
+}
+
+struct MutableAnimationInner {
+    state: Mutex<MutableAnimationState>,
+    value: Mutable<Percentage>,
+}
+
+// TODO deref to ReadOnlyMutable ?
+// TODO provide read_only() method ?
+#[derive(Clone)]
+pub struct MutableAnimation {
+    inner: Arc<MutableAnimationInner>,
+}
+
+impl fmt::Debug for MutableAnimation {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let state = self.inner.state.lock().unwrap_throw();
 
         fmt.debug_struct("MutableAnimation")
             .field("playing", &state.playing)
             .field("duration", &state.duration)
             .field("current", &self.inner.value.get())
             .field("end", &state.end)
+            .field("repeat", &state.repeat)
+            .field("yoyo", &state.yoyo)
+            .field("reduced_motion", &state.reduced_motion)
             .finish()
     }
 }
@@ -661,7 +1337,14 @@ impl MutableAnimation {
                 state: Mutex::new(MutableAnimationState {
                     playing: true,
                     duration: duration,
+                    start: initial,
                     end: initial,
+                    repeat: Repeat::Once,
+                    yoyo: false,
+                    remaining: Some(0),
+                    reduced_motion: None,
+                    completion: Completion::done(true),
+                    is_animating: Mutable::new(false),
                     _animating: None,
                 }),
                 value: Mutable::new(initial),
@@ -684,6 +1367,55 @@ impl MutableAnimation {
     #[inline]
     fn stop_animating(lock: &mut MutableAnimationState) {
         lock._animating = None;
+        lock.is_animating.set_neq(false);
+    }
+
+    // Starts a new target, the previous target is interrupted
+    fn new_target(lock: &mut MutableAnimationState, start: Percentage, end: Percentage) -> Completion {
+        lock.completion.resolve(false);
+        lock.completion = Completion::new();
+
+        lock.start = start;
+        lock.end = end;
+
+        lock.remaining = match lock.repeat {
+            Repeat::Once => Some(0),
+            Repeat::Times(times) => Some(times.saturating_sub(1)),
+            Repeat::Forever => None,
+        };
+
+        lock.completion.clone()
+    }
+
+    // Called when the animation reaches the end of a cycle
+    fn finish_cycle(&self, lock: &mut MutableAnimationState) {
+        let repeat = match lock.remaining {
+            Some(0) => false,
+            Some(ref mut remaining) => {
+                *remaining -= 1;
+                true
+            },
+            None => true,
+        };
+
+        if repeat && lock.start != lock.end {
+            if lock.yoyo {
+                std::mem::swap(&mut lock.start, &mut lock.end);
+
+            } else {
+                self.inner.value.set_neq(lock.start);
+            }
+
+            self.start_animating(lock);
+
+        } else {
+            lock.completion.resolve(true);
+        }
+    }
+
+    #[inline]
+    fn reduced_duration(lock: &MutableAnimationState) -> f64 {
+        lock.duration * reduced_motion_scale(lock.reduced_motion)
     }
 
     fn start_animating(&self, lock: &mut MutableAnimationState) {
@@ -693,21 +1425,24 @@ impl MutableAnimation {
             let end: f64 = lock.end.into_f64();
 
             if start != end {
-                if lock.duration > 0.0 {
-                    let duration = (end - start).abs() * lock.duration;
+                let duration = Self::reduced_duration(lock);
+
+                if duration > 0.0 {
+                    let duration = (end - start).abs() * duration;
 
                     let state = self.raw_clone();
 
+                    lock.is_animating.set_neq(true);
+
                     lock._animating = Some(OnTimestampDiff::new(move |diff| {
                         let diff = diff / duration;
 
                         // TODO test the performance of set_neq
                         if diff >= 1.0 {
-                            {
-                                let mut lock = state.inner.state.lock().unwrap_throw();
-                                Self::stop_animating(&mut lock);
-                            }
+                            let mut lock = state.inner.state.lock().unwrap_throw();
+                            Self::stop_animating(&mut lock);
                             state.inner.value.set_neq(Percentage::new_unchecked(end));
+                            state.finish_cycle(&mut lock);
 
                         } else {
                             state.inner.value.set_neq(Percentage::new_unchecked(range_inclusive(diff, start, end)));
@@ -717,11 +1452,14 @@ impl MutableAnimation {
                 } else {
                     Self::stop_animating(lock);
                     self.inner.value.set_neq(Percentage::new_unchecked(end));
+                    // Repeating doesn't make sense when the duration is 0
+                    lock.completion.resolve(true);
                 }
 
             } else {
                 // TODO is this necessary ?
                 Self::stop_animating(lock);
+                lock.completion.resolve(true);
             }
         }
     }
@@ -737,6 +1475,36 @@ impl MutableAnimation {
         }
     }
 
+    /// Sets how many times the animation plays when [`animate_to`](MutableAnimation::animate_to) is called.
+    ///
+    /// This applies to the next call to `animate_to`, not the current animation.
+    #[inline]
+    pub fn set_repeat(&self, repeat: Repeat) {
+        if let Repeat::Times(times) = repeat {
+            debug_assert!(times > 0);
+        }
+
+        let mut lock = self.inner.state.lock().unwrap_throw();
+        lock.repeat = repeat;
+    }
+
+    /// If `true` then every repetition plays in the opposite direction of the previous repetition,
+    /// otherwise every repetition starts again from the beginning.
+    #[inline]
+    pub fn set_yoyo(&self, yoyo: bool) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+        lock.yoyo = yoyo;
+    }
+
+    /// Overrides the global [`ReducedMotion`] policy for this animation, `None` uses the global policy.
+    ///
+    /// This applies to the next call to `animate_to`, not the current animation.
+    #[inline]
+    pub fn set_reduced_motion(&self, policy: Option<ReducedMotion>) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+        lock.reduced_motion = policy;
+    }
+
     #[inline]
     pub fn pause(&self) {
         let mut lock = self.inner.state.lock().unwrap_throw();
@@ -757,32 +1525,74 @@ impl MutableAnimation {
         }
     }
 
-    fn _jump_to(mut lock: &mut MutableAnimationState, mutable: &Mutable<Percentage>, end: Percentage) {
+    fn _jump_to(mut lock: &mut MutableAnimationState, mutable: &Mutable<Percentage>, end: Percentage) -> Completion {
         Self::stop_animating(&mut lock);
 
+        lock.completion.resolve(false);
+
+        // This avoids an allocation when it is called every frame (e.g. by Timeline)
+        if !lock.completion.is_success() {
+            lock.completion = Completion::done(true);
+        }
+
+        lock.start = end;
         lock.end = end;
+        lock.remaining = Some(0);
 
         mutable.set_neq(end);
+
+        lock.completion.clone()
     }
 
+    /// Immediately sets the animation to `end`, this interrupts the current animation.
     pub fn jump_to(&self, end: Percentage) {
         let mut lock = self.inner.state.lock().unwrap_throw();
 
         Self::_jump_to(&mut lock, &self.inner.value, end);
     }
 
-    pub fn animate_to(&self, end: Percentage) {
+    /// Animates from the current value to `end`.
+    ///
+    /// The returned [`AnimateTo`] future can be used to wait for the animation to finish,
+    /// but the animation plays even if the future is dropped.
+    ///
+    /// If the animation is already animating to `end` then it returns a future for the current animation.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// spawn_local(async move {
+    ///     if animation.animate_to(Percentage::END).await {
+    ///         // The animation has finished
+    ///     }
+    /// });
+    /// ```
+    pub fn animate_to(&self, end: Percentage) -> AnimateTo {
         let mut lock = self.inner.state.lock().unwrap_throw();
 
-        if lock.end != end {
-            if lock.duration <= 0.0 {
-                Self::_jump_to(&mut lock, &self.inner.value, end);
+        let completion = if lock.end != end {
+            if Self::reduced_duration(&lock) <= 0.0 {
+                Self::_jump_to(&mut lock, &self.inner.value, end)
 
             } else {
-                lock.end = end;
+                let completion = Self::new_target(&mut lock, self.inner.value.get(), end);
                 self.start_animating(&mut lock);
+                completion
             }
-        }
+
+        } else {
+            lock.completion.clone()
+        };
+
+        AnimateTo { completion }
+    }
+
+    /// Animates back to the opposite end, e.g. if it is animating to [`Percentage::END`] then it will
+    /// animate to [`Percentage::START`].
+    #[inline]
+    pub fn reverse(&self) -> AnimateTo {
+        let end = self.inner.state.lock().unwrap_throw().end;
+        self.animate_to(end.invert())
     }
 
     #[inline]
@@ -794,102 +1604,1018 @@ impl MutableAnimation {
     pub fn current_percentage(&self) -> Percentage {
         self.inner.value.get()
     }
-}
 
+    /// Whether the animation is currently moving, this is `false` if it is paused or finished.
+    #[inline]
+    pub fn is_playing(&self) -> bool {
+        self.inner.state.lock().unwrap_throw().is_animating.get()
+    }
 
-pub mod easing {
-    use super::Percentage;
+    /// Returns a signal which is `true` while the animation is moving.
+    #[inline]
+    pub fn playing_signal(&self) -> impl Signal<Item = bool> {
+        self.inner.state.lock().unwrap_throw().is_animating.signal()
+    }
 
-    // TODO should this use map rather than map_unchecked ?
     #[inline]
-    pub fn powi(p: Percentage, n: i32) -> Percentage {
-        p.map_unchecked(|p| p.powi(n))
+    fn duration(&self) -> f64 {
+        self.inner.state.lock().unwrap_throw().duration
+    }
+}
+
+
+struct TimelineEntry {
+    animation: MutableAnimation,
+    start: f64,
+    duration: f64,
+}
+
+struct TimelineState {
+    entries: Vec<TimelineEntry>,
+    // Where the next sequenced animation starts
+    cursor: f64,
+    // Where the previous animation started, used for parallel animations
+    previous_start: f64,
+    // The delay since the previous animation was added, it is added to the start of parallel animations
+    delay: f64,
+    duration: f64,
+    playing: bool,
+    // Overrides the global ReducedMotion policy
+    reduced_motion: Option<ReducedMotion>,
+    _animating: Option<OnTimestampDiff>,
+}
+
+struct TimelineInner {
+    state: Mutex<TimelineState>,
+    time: Mutable<f64>,
+}
+
+/// Controls multiple [`MutableAnimation`]s with a single clock.
+///
+/// Animations can be sequenced, run in parallel, delayed, and staggered. The timeline
+/// then plays, pauses, and seeks all of them together.
+///
+/// The durations of the animations are read when they are added to the timeline.
+///
+/// # Example
+///
+/// ```rust
+/// let fade = MutableAnimation::new(300.0);
+/// let slide = MutableAnimation::new(500.0);
+/// let items: Vec<MutableAnimation> = (0..10).map(|_| MutableAnimation::new(200.0)).collect();
+///
+/// let timeline = Timeline::new()
+///     .then(&fade)
+///     .with(&slide)
+///     .delay(100.0)
+///     .stagger(&items, 30.0);
+///
+/// timeline.play();
+///
+/// spawn_local(async move {
+///     timeline.finished().await;
+/// });
+/// ```
+#[derive(Clone)]
+pub struct Timeline {
+    inner: Arc<TimelineInner>,
+}
+
+impl fmt::Debug for Timeline {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let state = self.inner.state.lock().unwrap_throw();
+
+        fmt.debug_struct("Timeline")
+            .field("playing", &state.playing)
+            .field("duration", &state.duration)
+            .field("time", &self.inner.time.get())
+            .field("animations", &state.entries.len())
+            .finish()
     }
+}
 
+impl Timeline {
     #[inline]
-    pub fn cubic(p: Percentage) -> Percentage {
-        powi(p, 3)
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(TimelineInner {
+                state: Mutex::new(TimelineState {
+                    entries: vec![],
+                    cursor: 0.0,
+                    previous_start: 0.0,
+                    delay: 0.0,
+                    duration: 0.0,
+                    playing: false,
+                    reduced_motion: None,
+                    _animating: None,
+                }),
+                time: Mutable::new(0.0),
+            }),
+        }
     }
 
     #[inline]
-    pub fn out<F>(p: Percentage, f: F) -> Percentage where F: FnOnce(Percentage) -> Percentage {
-        f(p.invert()).invert()
+    fn raw_clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
     }
 
-    pub fn in_out<F>(p: Percentage, f: F) -> Percentage where F: FnOnce(Percentage) -> Percentage {
-        p.map_unchecked(|p| {
-            if p <= 0.5 {
-                f(Percentage::new_unchecked(p * 2.0)).into_f64() / 2.0
+    fn push(&self, animation: &MutableAnimation, start: f64) -> f64 {
+        let duration = animation.duration();
+        let end = start + duration;
 
-            } else {
-                1.0 - (f(Percentage::new_unchecked((1.0 - p) * 2.0)).into_f64() / 2.0)
+        {
+            let mut lock = self.inner.state.lock().unwrap_throw();
+
+            lock.entries.push(TimelineEntry {
+                animation: animation.raw_clone(),
+                start,
+                duration,
+            });
+
+            lock.previous_start = start;
+            lock.delay = 0.0;
+
+            if end > lock.duration {
+                lock.duration = end;
             }
-        })
+        }
+
+        Self::apply_entry(animation, start, duration, self.inner.time.get());
+
+        end
     }
 
+    /// Adds an animation which starts after the previous animations have finished.
+    pub fn then(self, animation: &MutableAnimation) -> Self {
+        let start = self.inner.state.lock().unwrap_throw().cursor;
 
-    /*pub struct Point {
-        pub x: f64,
-        pub y: f64,
-    }*/
+        let end = self.push(animation, start);
 
-    /*impl Point {
-        fn range_inclusive(percentage: f64, from: &Self, to: &Self) -> Self {
-            Point {
-                x: range_inclusive(percentage, from.x, to.x),
-                y: range_inclusive(percentage, from.y, to.y),
+        self.inner.state.lock().unwrap_throw().cursor = end;
+        self
+    }
+
+    /// Adds an animation which starts at the same time as the previous animation.
+    ///
+    /// A [`delay`](Timeline::delay) before it is added to the start, e.g. `.then(a).delay(100.0).with(b)` starts `b` 100 milliseconds after `a`.
+    pub fn with(self, animation: &MutableAnimation) -> Self {
+        let start = {
+            let lock = self.inner.state.lock().unwrap_throw();
+            lock.previous_start + lock.delay
+        };
+
+        let end = self.push(animation, start);
+
+        let mut lock = self.inner.state.lock().unwrap_throw();
+
+        if end > lock.cursor {
+            lock.cursor = end;
+        }
+
+        drop(lock);
+        self
+    }
+
+    /// Delays the next animation by `delay` milliseconds.
+    pub fn delay(self, delay: f64) -> Self {
+        debug_assert!(delay >= 0.0);
+
+        {
+            let mut lock = self.inner.state.lock().unwrap_throw();
+
+            lock.cursor += delay;
+            lock.delay += delay;
+
+            if lock.cursor > lock.duration {
+                lock.duration = lock.cursor;
             }
         }
-    }*/
 
-    /*#[inline]
-    fn get_values(start: f64, end: f64) -> (f64, f64, f64) {
-        let start = 3.0 * start;
-        let end = 3.0 * end;
-        let a = 1.0 - end + start;
-        let b = end - (2.0 * start);
-        (a, b, start)
+        self
     }
 
-    fn interpolate(p: f64, start: f64, end: f64) -> f64 {
-        let (a, b, c) = get_values(start, end);
-        ((a * p + b) * p + c) * p
+    /// Adds animations which each start `offset` milliseconds after the previous one.
+    ///
+    /// The next animation starts after all of the staggered animations have finished.
+    pub fn stagger(self, animations: &[MutableAnimation], offset: f64) -> Self {
+        debug_assert!(offset >= 0.0);
+
+        let start = self.inner.state.lock().unwrap_throw().cursor;
+
+        let mut cursor = start;
+
+        for (index, animation) in animations.iter().enumerate() {
+            let end = self.push(animation, start + (index as f64 * offset));
+
+            if end > cursor {
+                cursor = end;
+            }
+        }
+
+        self.inner.state.lock().unwrap_throw().cursor = cursor;
+        self
     }
 
-    fn get_slope(p: f64, start: f64, end: f64) -> f64 {
-        let (a, b, c) = get_values(start, end);
-        (3.0 * a * p * p) + (2.0 * b * p) + c
-    }*/
+    fn apply_entry(animation: &MutableAnimation, start: f64, duration: f64, time: f64) {
+        let percentage = if duration <= 0.0 {
+            if time >= start { 1.0 } else { 0.0 }
 
+        } else {
+            ((time - start) / duration).clamp(0.0, 1.0)
+        };
 
-    const EPSILON: f64 = 1e-6;
+        animation.jump_to(Percentage::new_unchecked(percentage));
+    }
 
-    pub struct CubicBezier {
-        ax: f64,
-        bx: f64,
-        cx: f64,
+    fn apply(lock: &TimelineState, time: f64) {
+        for entry in lock.entries.iter() {
+            Self::apply_entry(&entry.animation, entry.start, entry.duration, time);
+        }
+    }
 
-        ay: f64,
-        by: f64,
-        cy: f64,
+    fn set_time(&self, lock: &TimelineState, time: f64) {
+        Self::apply(lock, time);
+        self.inner.time.set_neq(time);
     }
 
-    impl CubicBezier {
-        pub fn new(x1: f64, y1: f64, x2: f64, y2: f64) -> Self {
-            assert!(x1 >= 0.0 && x1 <= 1.0);
-            assert!(y1 >= 0.0 && y1 <= 1.0);
-            assert!(x2 >= 0.0 && x2 <= 1.0);
-            assert!(y2 >= 0.0 && y2 <= 1.0);
+    fn start_animating(&self, lock: &mut TimelineState) {
+        let start = self.inner.time.get();
+        let scale = reduced_motion_scale(lock.reduced_motion);
 
-            let cx = 3.0 * x1;
-            let bx = 3.0 * (x2 - x1) - cx;
-            let ax = 1.0 - cx - bx;
+        if lock.playing && start < lock.duration && scale <= 0.0 {
+            lock.playing = false;
+            lock._animating = None;
+            self.set_time(lock, lock.duration);
 
-            let cy = 3.0 * y1;
-            let by = 3.0 * (y2 - y1) - cy;
-            let ay = 1.0 - cy - by;
+        } else if lock.playing && start < lock.duration {
+            let state = self.raw_clone();
 
-            Self { ax, bx, cx, ay, by, cy }
+            lock._animating = Some(OnTimestampDiff::new(move |diff| {
+                let mut lock = state.inner.state.lock().unwrap_throw();
+
+                let time = start + (diff / scale);
+
+                if time >= lock.duration {
+                    let duration = lock.duration;
+                    lock.playing = false;
+                    lock._animating = None;
+                    state.set_time(&lock, duration);
+
+                } else {
+                    state.set_time(&lock, time);
+                }
+            }));
+
+        } else {
+            lock.playing = false;
+            lock._animating = None;
+        }
+    }
+
+    /// Overrides the global [`ReducedMotion`] policy for this timeline, `None` uses the global policy.
+    ///
+    /// This applies the next time that the timeline is played, the policies of the individual animations are ignored.
+    #[inline]
+    pub fn set_reduced_motion(&self, policy: Option<ReducedMotion>) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+        lock.reduced_motion = policy;
+    }
+
+    /// Plays the timeline from the current time.
+    ///
+    /// If the timeline has already finished, it does nothing, use [`restart`](Timeline::restart) instead.
+    pub fn play(&self) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+
+        if !lock.playing {
+            lock.playing = true;
+            self.start_animating(&mut lock);
+        }
+    }
+
+    pub fn pause(&self) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+
+        if lock.playing {
+            lock.playing = false;
+            lock._animating = None;
+        }
+    }
+
+    /// Plays the timeline from the beginning.
+    pub fn restart(&self) {
+        self.seek(0.0);
+        self.play();
+    }
+
+    /// Jumps to `time` milliseconds, the animations are updated immediately.
+    pub fn seek(&self, time: f64) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+
+        let time = time.clamp(0.0, lock.duration);
+
+        lock._animating = None;
+
+        self.set_time(&lock, time);
+        self.start_animating(&mut lock);
+    }
+
+    #[inline]
+    pub fn is_playing(&self) -> bool {
+        self.inner.state.lock().unwrap_throw().playing
+    }
+
+    /// The total duration of the timeline, in milliseconds.
+    #[inline]
+    pub fn duration(&self) -> f64 {
+        self.inner.state.lock().unwrap_throw().duration
+    }
+
+    /// The current time of the timeline, in milliseconds.
+    #[inline]
+    pub fn time(&self) -> f64 {
+        self.inner.time.get()
+    }
+
+    #[inline]
+    pub fn time_signal(&self) -> impl Signal<Item = f64> {
+        self.inner.time.signal()
+    }
+
+    /// Returns a future which resolves when the timeline reaches the end.
+    pub fn finished(&self) -> impl Future<Output = ()> {
+        let inner = self.inner.clone();
+
+        self.inner.time.signal()
+            .map(move |time| time >= inner.state.lock().unwrap_throw().duration)
+            .wait_for(true)
+            .map(|_| ())
+    }
+}
+
+impl Default for Timeline {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/// The physical properties of a [`SpringAnimation`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spring {
+    /// How strongly the spring pulls towards the target.
+    pub stiffness: f64,
+    /// How strongly the spring resists motion, higher values have less bouncing.
+    pub damping: f64,
+    pub mass: f64,
+    /// The spring stops animating when both the distance to the target and the velocity are below this.
+    pub tolerance: f64,
+}
+
+impl Spring {
+    pub const DEFAULT: Spring = Spring { stiffness: 170.0, damping: 26.0, mass: 1.0, tolerance: 0.01 };
+    pub const GENTLE: Spring = Spring { stiffness: 120.0, damping: 14.0, mass: 1.0, tolerance: 0.01 };
+    pub const WOBBLY: Spring = Spring { stiffness: 180.0, damping: 12.0, mass: 1.0, tolerance: 0.01 };
+    pub const STIFF: Spring = Spring { stiffness: 210.0, damping: 20.0, mass: 1.0, tolerance: 0.01 };
+    pub const SLOW: Spring = Spring { stiffness: 280.0, damping: 60.0, mass: 1.0, tolerance: 0.01 };
+}
+
+impl Default for Spring {
+    #[inline]
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+
+#[derive(Debug)]
+pub struct SpringAnimationSignal(MutableSignal<f64>);
+
+impl Signal for SpringAnimationSignal {
+    type Item = f64;
+
+    #[inline]
+    fn poll_change(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.0.poll_change_unpin(cx)
+    }
+}
+
+
+// The simulation uses fixed steps (in milliseconds) so that it is stable with stiff springs
+const SPRING_STEP: f64 = 1.0;
+
+// Prevents the spring from jumping when the page was in the background
+const SPRING_MAX_FRAME: f64 = 64.0;
+
+struct SpringAnimationState {
+    spring: Spring,
+    target: f64,
+    velocity: f64,
+    // Overrides the global ReducedMotion policy
+    reduced_motion: Option<ReducedMotion>,
+    _animating: Option<OnTimestampDiff>,
+}
+
+struct SpringAnimationInner {
+    state: Mutex<SpringAnimationState>,
+    value: Mutable<f64>,
+}
+
+/// An animation which uses spring physics to move an `f64` towards a target.
+///
+/// Unlike [`MutableAnimation`], it doesn't have a duration. Instead it moves according to the
+/// [`Spring`], and it stops once it has settled on the target.
+///
+/// If the target is changed while the spring is moving, it keeps its current velocity, so the
+/// motion is smooth even if it is interrupted.
+///
+/// # Example
+///
+/// ```rust
+/// let spring = SpringAnimation::new(Spring::WOBBLY, 0.0);
+///
+/// html!("div", {
+///     .style_signal("transform", spring.signal().map(|x| format!("translateX({}px)", x)))
+///
+///     .event(clone!(spring => move |_: events::PointerUp| {
+///         spring.set_velocity(release_velocity);
+///         spring.animate_to(0.0);
+///     }))
+/// })
+/// ```
+#[derive(Clone)]
+pub struct SpringAnimation {
+    inner: Arc<SpringAnimationInner>,
+}
+
+impl fmt::Debug for SpringAnimation {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let state = self.inner.state.lock().unwrap_throw();
+
+        fmt.debug_struct("SpringAnimation")
+            .field("spring", &state.spring)
+            .field("current", &self.inner.value.get())
+            .field("target", &state.target)
+            .field("velocity", &state.velocity)
+            .finish()
+    }
+}
+
+impl SpringAnimation {
+    #[inline]
+    pub fn new(spring: Spring, initial: f64) -> Self {
+        debug_assert!(spring.mass > 0.0);
+
+        Self {
+            inner: Arc::new(SpringAnimationInner {
+                state: Mutex::new(SpringAnimationState {
+                    spring,
+                    target: initial,
+                    velocity: 0.0,
+                    reduced_motion: None,
+                    _animating: None,
+                }),
+                value: Mutable::new(initial),
+            }),
+        }
+    }
+
+    #[inline]
+    fn raw_clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+
+    fn is_settled(spring: &Spring, value: f64, target: f64, velocity: f64) -> bool {
+        (target - value).abs() < spring.tolerance && velocity.abs() < spring.tolerance
+    }
+
+    // Returns the new value and velocity after `time` milliseconds
+    fn step(spring: &Spring, mut value: f64, target: f64, mut velocity: f64, mut time: f64) -> (f64, f64) {
+        while time > 0.0 {
+            let dt = time.min(SPRING_STEP);
+
+            let force = -spring.stiffness * (value - target) - spring.damping * velocity;
+
+            // Semi-implicit Euler, the velocity is in units per second
+            velocity += (force / spring.mass) * (dt / 1000.0);
+            value += velocity * (dt / 1000.0);
+
+            time -= dt;
+        }
+
+        (value, velocity)
+    }
+
+    fn start_animating(&self, lock: &mut SpringAnimationState) {
+        let scale = reduced_motion_scale(lock.reduced_motion);
+
+        if scale <= 0.0 {
+            lock._animating = None;
+            lock.velocity = 0.0;
+            self.inner.value.set_neq(lock.target);
+
+        } else if lock._animating.is_none() {
+            let state = self.raw_clone();
+
+            let mut previous = 0.0;
+
+            lock._animating = Some(OnTimestampDiff::new(move |diff| {
+                let time = (diff - previous).min(SPRING_MAX_FRAME) / scale;
+                previous = diff;
+
+                let value = {
+                    let mut lock = state.inner.state.lock().unwrap_throw();
+
+                    let (value, velocity) = Self::step(&lock.spring, state.inner.value.get(), lock.target, lock.velocity, time);
+
+                    if Self::is_settled(&lock.spring, value, lock.target, velocity) {
+                        lock.velocity = 0.0;
+                        lock._animating = None;
+                        lock.target
+
+                    } else {
+                        lock.velocity = velocity;
+                        value
+                    }
+                };
+
+                state.inner.value.set_neq(value);
+            }));
+        }
+    }
+
+    /// Moves towards the target, starting with the current velocity.
+    pub fn animate_to(&self, target: f64) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+
+        lock.target = target;
+
+        if !Self::is_settled(&lock.spring, self.inner.value.get(), target, lock.velocity) {
+            self.start_animating(&mut lock);
+        }
+    }
+
+    /// Immediately sets the value to the target and stops moving.
+    pub fn jump_to(&self, target: f64) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+
+        lock._animating = None;
+        lock.target = target;
+        lock.velocity = 0.0;
+
+        self.inner.value.set_neq(target);
+    }
+
+    /// Sets the velocity (in units per second), this is useful when releasing a drag gesture.
+    pub fn set_velocity(&self, velocity: f64) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+
+        lock.velocity = velocity;
+
+        if !Self::is_settled(&lock.spring, self.inner.value.get(), lock.target, velocity) {
+            self.start_animating(&mut lock);
+        }
+    }
+
+    /// Changes the spring, this takes effect immediately even if it is moving.
+    #[inline]
+    pub fn set_spring(&self, spring: Spring) {
+        debug_assert!(spring.mass > 0.0);
+
+        let mut lock = self.inner.state.lock().unwrap_throw();
+        lock.spring = spring;
+    }
+
+    /// Overrides the global [`ReducedMotion`] policy for this spring, `None` uses the global policy.
+    ///
+    /// This applies the next time that the spring starts moving.
+    #[inline]
+    pub fn set_reduced_motion(&self, policy: Option<ReducedMotion>) {
+        let mut lock = self.inner.state.lock().unwrap_throw();
+        lock.reduced_motion = policy;
+    }
+
+    #[inline]
+    pub fn velocity(&self) -> f64 {
+        self.inner.state.lock().unwrap_throw().velocity
+    }
+
+    #[inline]
+    pub fn target(&self) -> f64 {
+        self.inner.state.lock().unwrap_throw().target
+    }
+
+    #[inline]
+    pub fn current(&self) -> f64 {
+        self.inner.value.get()
+    }
+
+    #[inline]
+    pub fn signal(&self) -> SpringAnimationSignal {
+        SpringAnimationSignal(self.inner.value.signal())
+    }
+}
+
+
+pub mod easing {
+    use std::f64::consts::PI;
+    use super::Percentage;
+
+    // TODO should this use map rather than map_unchecked ?
+    #[inline]
+    pub fn powi(p: Percentage, n: i32) -> Percentage {
+        p.map_unchecked(|p| p.powi(n))
+    }
+
+    #[inline]
+    pub fn cubic(p: Percentage) -> Percentage {
+        powi(p, 3)
+    }
+
+    #[inline]
+    pub fn out<F>(p: Percentage, f: F) -> Percentage where F: FnOnce(Percentage) -> Percentage {
+        f(p.invert()).invert()
+    }
+
+    pub fn in_out<F>(p: Percentage, f: F) -> Percentage where F: FnOnce(Percentage) -> Percentage {
+        p.map_unchecked(|p| {
+            if p <= 0.5 {
+                f(Percentage::new_unchecked(p * 2.0)).into_f64() / 2.0
+
+            } else {
+                1.0 - (f(Percentage::new_unchecked((1.0 - p) * 2.0)).into_f64() / 2.0)
+            }
+        })
+    }
+
+    #[inline]
+    pub fn quad(p: Percentage) -> Percentage {
+        powi(p, 2)
+    }
+
+    #[inline]
+    pub fn quart(p: Percentage) -> Percentage {
+        powi(p, 4)
+    }
+
+    #[inline]
+    pub fn quint(p: Percentage) -> Percentage {
+        powi(p, 5)
+    }
+
+    #[inline]
+    pub fn sine(p: Percentage) -> Percentage {
+        p.map_unchecked(|p| 1.0 - (p * PI / 2.0).cos())
+    }
+
+    #[inline]
+    pub fn expo(p: Percentage) -> Percentage {
+        p.map_unchecked(|p| {
+            if p == 0.0 {
+                0.0
+
+            } else {
+                2.0f64.powf(10.0 * p - 10.0)
+            }
+        })
+    }
+
+    #[inline]
+    pub fn circ(p: Percentage) -> Percentage {
+        p.map_unchecked(|p| 1.0 - (1.0 - p * p).sqrt())
+    }
+
+    /// Moves slightly backwards before moving forwards, so it goes below `0.0`.
+    #[inline]
+    pub fn back(p: Percentage) -> Percentage {
+        const C1: f64 = 1.70158;
+        const C3: f64 = C1 + 1.0;
+
+        p.map_unchecked(|p| C3 * p * p * p - C1 * p * p)
+    }
+
+    /// Oscillates like a spring, so it goes below `0.0`.
+    #[inline]
+    pub fn elastic(p: Percentage) -> Percentage {
+        const C4: f64 = (2.0 * PI) / 3.0;
+
+        p.map_unchecked(|p| {
+            if p == 0.0 {
+                0.0
+
+            } else if p == 1.0 {
+                1.0
+
+            } else {
+                -(2.0f64.powf(10.0 * p - 10.0)) * ((p * 10.0 - 10.75) * C4).sin()
+            }
+        })
+    }
+
+    /// Bounces at the start, use `out(p, bounce)` to bounce at the end.
+    #[inline]
+    pub fn bounce(p: Percentage) -> Percentage {
+        const N1: f64 = 7.5625;
+        const D1: f64 = 2.75;
+
+        fn bounce_out(p: f64) -> f64 {
+            if p < 1.0 / D1 {
+                N1 * p * p
+
+            } else if p < 2.0 / D1 {
+                let p = p - 1.5 / D1;
+                N1 * p * p + 0.75
+
+            } else if p < 2.5 / D1 {
+                let p = p - 2.25 / D1;
+                N1 * p * p + 0.9375
+
+            } else {
+                let p = p - 2.625 / D1;
+                N1 * p * p + 0.984375
+            }
+        }
+
+        p.map_unchecked(|p| 1.0 - bounce_out(1.0 - p))
+    }
+
+
+    /// The [`<jump-term>`](https://developer.mozilla.org/en-US/docs/Web/CSS/easing-function/steps#step-position) of [`steps`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum StepPosition {
+        /// The first jump happens at the start.
+        JumpStart,
+        /// The last jump happens at the end, this is the default.
+        #[default]
+        JumpEnd,
+        /// There are no jumps at the start or end.
+        JumpNone,
+        /// There are jumps at both the start and end.
+        JumpBoth,
+    }
+
+    /// The same as the CSS [`steps()`](https://developer.mozilla.org/en-US/docs/Web/CSS/easing-function/steps) function.
+    pub fn steps(p: Percentage, steps: u32, position: StepPosition) -> Percentage {
+        debug_assert!(steps > 0);
+        debug_assert!(steps > 1 || position != StepPosition::JumpNone);
+
+        let steps = steps as f64;
+
+        p.map_unchecked(|p| {
+            let mut step = (p * steps).floor();
+
+            if let StepPosition::JumpStart | StepPosition::JumpBoth = position {
+                step += 1.0;
+            }
+
+            let jumps = match position {
+                StepPosition::JumpStart | StepPosition::JumpEnd => steps,
+                StepPosition::JumpNone => steps - 1.0,
+                StepPosition::JumpBoth => steps + 1.0,
+            };
+
+            if p >= 0.0 && step < 0.0 {
+                step = 0.0;
+            }
+
+            if p <= 1.0 && step > jumps {
+                step = jumps;
+            }
+
+            step / jumps
+        })
+    }
+
+
+    /// The same as the CSS [`linear()`](https://developer.mozilla.org/en-US/docs/Web/CSS/easing-function/linear) function.
+    ///
+    /// It linearly interpolates between multiple points.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// // linear(0, 0.25 75%, 1)
+    /// let easing = Linear::with_stops(&[(0.0, None), (0.25, Some(0.75)), (1.0, None)]);
+    ///
+    /// easing.easing(p)
+    /// ```
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Linear {
+        // (input, output)
+        points: Vec<(f64, f64)>,
+    }
+
+    impl Linear {
+        /// Creates an easing from outputs which are spaced evenly.
+        pub fn new(outputs: &[f64]) -> Self {
+            let stops: Vec<(f64, Option<f64>)> = outputs.iter().map(|output| (*output, None)).collect();
+            Self::with_stops(&stops)
+        }
+
+        /// Creates an easing from `(output, input)` pairs, where the input is optional.
+        ///
+        /// Missing inputs are filled in the same way as CSS.
+        pub fn with_stops(stops: &[(f64, Option<f64>)]) -> Self {
+            assert!(stops.len() >= 2, "linear easing must have at least 2 stops");
+
+            let len = stops.len();
+
+            let mut inputs: Vec<Option<f64>> = stops.iter().map(|(_, input)| *input).collect();
+
+            if inputs[0].is_none() {
+                inputs[0] = Some(0.0);
+            }
+
+            if inputs[len - 1].is_none() {
+                inputs[len - 1] = Some(1.0);
+            }
+
+            // Inputs must not decrease
+            let mut largest = f64::NEG_INFINITY;
+
+            for input in inputs.iter_mut().flatten() {
+                if *input < largest {
+                    *input = largest;
+                }
+
+                largest = *input;
+            }
+
+            // Missing inputs are spaced evenly between the surrounding inputs
+            let mut index = 0;
+
+            while index < len {
+                if inputs[index].is_none() {
+                    let start_index = index - 1;
+                    let start = inputs[start_index].unwrap();
+
+                    let end_index = (index..len).find(|i| inputs[*i].is_some()).unwrap();
+                    let end = inputs[end_index].unwrap();
+
+                    let count = (end_index - start_index) as f64;
+
+                    for (i, input) in inputs.iter_mut().enumerate().take(end_index).skip(index) {
+                        *input = Some(start + (end - start) * ((i - start_index) as f64 / count));
+                    }
+
+                    index = end_index;
+
+                } else {
+                    index += 1;
+                }
+            }
+
+            Self {
+                points: inputs.into_iter().zip(stops.iter()).map(|(input, (output, _))| (input.unwrap(), *output)).collect(),
+            }
+        }
+
+        pub fn easing(&self, p: Percentage) -> Percentage {
+            p.map_unchecked(|p| {
+                let first = self.points[0];
+                let last = self.points[self.points.len() - 1];
+
+                if p <= first.0 {
+                    first.1
+
+                } else if p >= last.0 {
+                    last.1
+
+                } else {
+                    // The last point whose input is less than or equal to p
+                    let index = self.points.iter().rposition(|(input, _)| *input <= p).unwrap();
+
+                    let (start_input, start_output) = self.points[index];
+                    let (end_input, end_output) = self.points[index + 1];
+
+                    if end_input == start_input {
+                        end_output
+
+                    } else {
+                        super::range_inclusive((p - start_input) / (end_input - start_input), start_output, end_output)
+                    }
+                }
+            })
+        }
+    }
+
+
+    /// The same as the CSS `ease` timing function.
+    #[inline]
+    pub fn ease(p: Percentage) -> Percentage {
+        CubicBezier::ease().easing(p)
+    }
+
+    /// The same as the CSS `ease-in` timing function.
+    #[inline]
+    pub fn ease_in(p: Percentage) -> Percentage {
+        CubicBezier::ease_in().easing(p)
+    }
+
+    /// The same as the CSS `ease-out` timing function.
+    #[inline]
+    pub fn ease_out(p: Percentage) -> Percentage {
+        CubicBezier::ease_out().easing(p)
+    }
+
+    /// The same as the CSS `ease-in-out` timing function.
+    #[inline]
+    pub fn ease_in_out(p: Percentage) -> Percentage {
+        CubicBezier::ease_in_out().easing(p)
+    }
+
+
+    /*pub struct Point {
+        pub x: f64,
+        pub y: f64,
+    }*/
+
+    /*impl Point {
+        fn range_inclusive(percentage: f64, from: &Self, to: &Self) -> Self {
+            Point {
+                x: range_inclusive(percentage, from.x, to.x),
+                y: range_inclusive(percentage, from.y, to.y),
+            }
+        }
+    }*/
+
+    /*#[inline]
+    fn get_values(start: f64, end: f64) -> (f64, f64, f64) {
+        let start = 3.0 * start;
+        let end = 3.0 * end;
+        let a = 1.0 - end + start;
+        let b = end - (2.0 * start);
+        (a, b, start)
+    }
+
+    fn interpolate(p: f64, start: f64, end: f64) -> f64 {
+        let (a, b, c) = get_values(start, end);
+        ((a * p + b) * p + c) * p
+    }
+
+    fn get_slope(p: f64, start: f64, end: f64) -> f64 {
+        let (a, b, c) = get_values(start, end);
+        (3.0 * a * p * p) + (2.0 * b * p) + c
+    }*/
+
+
+    const EPSILON: f64 = 1e-6;
+
+    pub struct CubicBezier {
+        ax: f64,
+        bx: f64,
+        cx: f64,
+
+        ay: f64,
+        by: f64,
+        cy: f64,
+    }
+
+    impl CubicBezier {
+        pub fn new(x1: f64, y1: f64, x2: f64, y2: f64) -> Self {
+            assert!(x1 >= 0.0 && x1 <= 1.0);
+            assert!(y1 >= 0.0 && y1 <= 1.0);
+            assert!(x2 >= 0.0 && x2 <= 1.0);
+            assert!(y2 >= 0.0 && y2 <= 1.0);
+
+            let cx = 3.0 * x1;
+            let bx = 3.0 * (x2 - x1) - cx;
+            let ax = 1.0 - cx - bx;
+
+            let cy = 3.0 * y1;
+            let by = 3.0 * (y2 - y1) - cy;
+            let ay = 1.0 - cy - by;
+
+            Self { ax, bx, cx, ay, by, cy }
+        }
+
+        /// The same as the CSS `ease` timing function.
+        #[inline]
+        pub fn ease() -> Self {
+            Self::new(0.25, 0.1, 0.25, 1.0)
+        }
+
+        /// The same as the CSS `ease-in` timing function.
+        #[inline]
+        pub fn ease_in() -> Self {
+            Self::new(0.42, 0.0, 1.0, 1.0)
+        }
+
+        /// The same as the CSS `ease-out` timing function.
+        #[inline]
+        pub fn ease_out() -> Self {
+            Self::new(0.0, 0.0, 0.58, 1.0)
+        }
+
+        /// The same as the CSS `ease-in-out` timing function.
+        #[inline]
+        pub fn ease_in_out() -> Self {
+            Self::new(0.42, 0.0, 0.58, 1.0)
         }
 
         /*fn values(p: f64) -> (f64, f64, f64, f64) {
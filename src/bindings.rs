@@ -1,7 +1,7 @@
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::{JsCast, intern};
 use js_sys::Reflect;
-use web_sys::{HtmlElement, Element, Node, Window, History, Document, Text, Comment, DomTokenList, CssStyleSheet, CssStyleDeclaration, HtmlStyleElement, CssRule};
+use web_sys::{Animation, HtmlElement, Element, Node, MediaQueryList, Window, History, Document, Text, Comment, DomTokenList, CssStyleSheet, CssStyleDeclaration, HtmlStyleElement, CssRule, ShadowRoot};
 use crate::utils::UnwrapJsExt;
 
 
@@ -13,6 +13,21 @@ pub(crate) fn set_property(obj: &JsValue, name: &str, value: &JsValue) {
 }
 
 
+// Element.animate is an unstable API in web-sys, so it is bound manually
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(extends = Element)]
+    type AnimatableElement;
+
+    #[wasm_bindgen(method, js_name = animate)]
+    fn animate(this: &AnimatableElement, keyframes: &js_sys::Array, options: &js_sys::Object) -> Animation;
+}
+
+pub(crate) fn animate(elem: &Element, keyframes: &js_sys::Array, options: &js_sys::Object) -> Animation {
+    elem.unchecked_ref::<AnimatableElement>().animate(keyframes, options)
+}
+
+
 thread_local! {
     pub static WINDOW: Window = web_sys::window().unwrap_throw();
     static DOCUMENT: Document = WINDOW.with(|w| w.document().unwrap_throw());
@@ -32,6 +47,11 @@ pub(crate) fn current_url() -> String {
     WINDOW.with(|w| w.location().href().unwrap_js())
 }
 
+#[track_caller]
+pub(crate) fn match_media(query: &str) -> MediaQueryList {
+    WINDOW.with(|w| w.match_media(query).unwrap_js().unwrap_throw())
+}
+
 #[track_caller]
 pub(crate) fn go_to_url(url: &str) {
     HISTORY.with(|h| {
@@ -47,13 +67,17 @@ pub(crate) fn replace_url(url: &str) {
 }
 
 #[track_caller]
-pub(crate) fn create_stylesheet(css: Option<&str>) -> CssStyleSheet {
+pub(crate) fn create_stylesheet(css: Option<&str>, nonce: Option<&str>) -> CssStyleSheet {
     DOCUMENT.with(|document| {
         // TODO use createElementNS ?
         // TODO use dyn_into ?
         let e: HtmlStyleElement = document.create_element("style").unwrap_js().unchecked_into();
         e.set_type("text/css");
 
+        if let Some(nonce) = nonce {
+            set_attribute(&e, "nonce", nonce);
+        }
+
         if let Some(css) = css {
             e.set_text_content(Some(css));
         }
@@ -64,6 +88,37 @@ pub(crate) fn create_stylesheet(css: Option<&str>) -> CssStyleSheet {
     })
 }
 
+#[track_caller]
+pub(crate) fn create_constructable_stylesheet() -> CssStyleSheet {
+    CssStyleSheet::new().unwrap_js()
+}
+
+pub(crate) fn adopt_stylesheet(root: &ShadowRoot, sheet: &CssStyleSheet) {
+    let sheets = root.adopted_style_sheets();
+
+    if !sheets.includes(sheet, 0) {
+        sheets.push(sheet);
+        // Older browsers return a frozen copy of the array, so it must be set again
+        root.set_adopted_style_sheets(&sheets);
+    }
+}
+
+// Appends all of the rules in `css` to the end of the stylesheet
+#[track_caller]
+pub(crate) fn append_stylesheet(sheet: &CssStyleSheet, css: &str) {
+    // This uses a temporary stylesheet so that the browser will parse the CSS into separate rules
+    let parsed = create_constructable_stylesheet();
+    parsed.replace_sync(css).unwrap_js();
+
+    let rules = parsed.css_rules().unwrap_js();
+
+    for index in 0..rules.length() {
+        let rule = rules.get(index).unwrap_throw();
+        let length = sheet.css_rules().unwrap_js().length();
+        sheet.insert_rule_with_index(&rule.css_text(), length).unwrap_js();
+    }
+}
+
 #[track_caller]
 pub(crate) fn make_rule(sheet: &CssStyleSheet, rule: &str) -> Result<CssRule, JsValue> {
     let rules = sheet.css_rules().unwrap_js();
@@ -74,6 +129,22 @@ pub(crate) fn make_rule(sheet: &CssStyleSheet, rule: &str) -> Result<CssRule, Js
     Ok(rules.get(length).unwrap_throw())
 }
 
+#[track_caller]
+pub(crate) fn remove_rule(rule: &CssRule) {
+    if let Some(sheet) = rule.parent_style_sheet() {
+        let rules = sheet.css_rules().unwrap_js();
+
+        // This searches backwards because newer rules are more likely to be removed
+        // TODO make this more efficient ?
+        for index in (0..rules.length()).rev() {
+            if rules.get(index).as_ref() == Some(rule) {
+                sheet.delete_rule(index).unwrap_js();
+                break;
+            }
+        }
+    }
+}
+
 
 pub(crate) fn get_element_by_id(id: &str) -> Element {
     DOCUMENT.with(|d| d.get_element_by_id(id).unwrap_throw())
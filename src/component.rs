@@ -0,0 +1,126 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use web_sys::Node;
+
+use crate::dom::Dom;
+use crate::utils::FnDiscard;
+
+
+/// An optional trait which gives components a consistent shape.
+///
+/// A component is a struct which contains the component's local state (usually `Mutable`s).
+/// It is created from its [`Props`](Component::Props), and then it is rendered into a [`Dom`].
+///
+/// The component is kept alive until its [`Dom`] is removed, so you don't need to store it anywhere.
+///
+/// Use [`component`] to create the [`Dom`] for a component.
+///
+/// # Example
+///
+/// ```rust
+/// struct Counter {
+///     count: Mutable<i32>,
+/// }
+///
+/// impl Component for Counter {
+///     type Props = i32;
+///
+///     fn new(initial: i32) -> Self {
+///         Self { count: Mutable::new(initial) }
+///     }
+///
+///     fn render(this: &Rc<Self>) -> Dom {
+///         html!("button", {
+///             .text_signal(this.count.signal().map(|count| count.to_string()))
+///             .event(clone!(this => move |_: events::Click| {
+///                 this.count.replace_with(|count| *count + 1);
+///             }))
+///         })
+///     }
+/// }
+///
+/// html!("div", {
+///     .child(component::<Counter>(5))
+/// })
+/// ```
+pub trait Component: Sized + 'static {
+    type Props;
+
+    /// Creates the component's local state from its props.
+    fn new(props: Self::Props) -> Self;
+
+    fn render(this: &Rc<Self>) -> Dom;
+
+    /// Called after the component's [`Dom`] is inserted into the DOM.
+    #[inline]
+    fn mounted(_this: &Rc<Self>) {}
+
+    /// Called after the component's [`Dom`] is removed from the DOM.
+    #[inline]
+    fn unmounted(_this: &Rc<Self>) {}
+
+    /// The name of the component, this is passed to the hook which is set with [`set_component_hook`].
+    #[inline]
+    fn name() -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+
+type ComponentHook = fn(&'static str, &Node);
+
+thread_local! {
+    static COMPONENT_HOOK: Cell<Option<ComponentHook>> = const { Cell::new(None) };
+}
+
+/// Sets a hook which is called with the name and DOM node of every [`Component`] after it is rendered.
+///
+/// This can be used to add cross-cutting features to every component, such as names for devtools.
+///
+/// # Example
+///
+/// ```rust
+/// set_component_hook(|name, node| {
+///     if cfg!(debug_assertions) {
+///         if let Some(element) = node.dyn_ref::<Element>() {
+///             element.set_attribute("data-component", name).unwrap_throw();
+///         }
+///     }
+/// });
+/// ```
+#[inline]
+pub fn set_component_hook(hook: fn(&'static str, &Node)) {
+    COMPONENT_HOOK.with(|component_hook| component_hook.set(Some(hook)));
+}
+
+
+/// Creates the [`Dom`] for a [`Component`].
+///
+/// It calls [`Component::new`] with the props, and then [`Component::render`]. The component
+/// is kept alive until the [`Dom`] is removed.
+#[inline]
+pub fn component<C>(props: C::Props) -> Dom where C: Component {
+    let this = Rc::new(C::new(props));
+
+    let mut dom = C::render(&this);
+
+    {
+        let this = this.clone();
+
+        dom.callbacks.after_insert(move |_| {
+            C::mounted(&this);
+        });
+    }
+
+    // This also keeps the component alive until the Dom is removed
+    dom.callbacks.after_remove(FnDiscard::new(move || {
+        C::unmounted(&this);
+    }));
+
+    if let Some(hook) = COMPONENT_HOOK.with(|hook| hook.get()) {
+        hook(C::name(), &dom.element);
+    }
+
+    dom
+}
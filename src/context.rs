@@ -0,0 +1,253 @@
+use std::any::{Any, TypeId};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_signals::signal::{Signal, Mutable, MutableSignalCloned};
+use js_sys::Reflect;
+use wasm_bindgen::{JsValue, JsCast, intern};
+use web_sys::{Node, ShadowRoot};
+
+use crate::bindings;
+use crate::dom::DomBuilder;
+use crate::operations::for_each;
+use crate::utils::FnDiscard;
+
+
+// The id is stored on the DOM node, so that it can be found by walking up the DOM tree
+const CONTEXT_ID: &str = "__dominatorContextId";
+
+// The values which are provided by a single node, keyed by their type
+type Provided = HashMap<TypeId, Box<dyn Any>>;
+
+thread_local! {
+    static CONTEXT_ID_COUNTER: Cell<u32> = const { Cell::new(0) };
+    static PROVIDERS: RefCell<HashMap<u32, Provided>> = RefCell::new(HashMap::new());
+}
+
+fn provider_id(node: &Node) -> Option<u32> {
+    let id = Reflect::get(node, &JsValue::from(intern(CONTEXT_ID))).ok()?;
+    id.as_f64().map(|id| id as u32)
+}
+
+fn make_provider_id(node: &Node) -> u32 {
+    if let Some(id) = provider_id(node) {
+        id
+
+    } else {
+        let id = CONTEXT_ID_COUNTER.with(|counter| {
+            let id = counter.get();
+            // TODO check for overflow ?
+            counter.set(id + 1);
+            id
+        });
+
+        bindings::set_property(node, intern(CONTEXT_ID), &JsValue::from(id));
+        id
+    }
+}
+
+fn parent(node: &Node) -> Option<Node> {
+    let parent = node.parent_node()?;
+
+    // Contexts are inherited through shadow roots
+    if let Some(shadow) = parent.dyn_ref::<ShadowRoot>() {
+        Some(shadow.host().into())
+
+    } else {
+        Some(parent)
+    }
+}
+
+fn find_provider<T>(node: &Node) -> Option<Mutable<T>> where T: 'static {
+    let mut node = parent(node);
+
+    while let Some(current) = node {
+        if let Some(id) = provider_id(&current) {
+            let found = PROVIDERS.with(|providers| {
+                providers.borrow()
+                    .get(&id)
+                    .and_then(|values| values.get(&TypeId::of::<T>()))
+                    .and_then(|value| value.downcast_ref::<Mutable<T>>())
+                    .cloned()
+            });
+
+            if found.is_some() {
+                return found;
+            }
+        }
+
+        node = parent(&current);
+    }
+
+    None
+}
+
+
+/// The value of a context, which is retrieved with [`DomBuilder::with_context`].
+///
+/// It is `None` until the element is inserted into the DOM, and it is also `None`
+/// if there isn't a parent which provides the context.
+#[derive(Debug)]
+pub struct ContextValue<T> {
+    value: Mutable<Option<T>>,
+}
+
+impl<T> ContextValue<T> {
+    /// Returns a signal of the context's value.
+    #[inline]
+    pub fn signal_ref<B, F>(&self, f: F) -> impl Signal<Item = B> where F: FnMut(&Option<T>) -> B {
+        self.value.signal_ref(f)
+    }
+}
+
+impl<T> ContextValue<T> where T: Clone {
+    #[inline]
+    pub fn get(&self) -> Option<T> {
+        self.value.get_cloned()
+    }
+
+    /// Returns a signal of the context's value.
+    #[inline]
+    pub fn signal(&self) -> ContextSignal<T> {
+        ContextSignal {
+            signal: self.value.signal_cloned(),
+        }
+    }
+}
+
+impl<T> Clone for ContextValue<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self { value: self.value.clone() }
+    }
+}
+
+
+#[derive(Debug)]
+#[must_use = "Signals do nothing unless polled"]
+pub struct ContextSignal<T> {
+    signal: MutableSignalCloned<Option<T>>,
+}
+
+impl<T> Signal for ContextSignal<T> where T: Clone {
+    type Item = Option<T>;
+
+    #[inline]
+    fn poll_change(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.signal).poll_change(cx)
+    }
+}
+
+
+impl<A> DomBuilder<A> where A: AsRef<Node> {
+    fn provider(&mut self) -> u32 {
+        make_provider_id(self.element.as_ref())
+    }
+
+    fn insert_provider<T>(&mut self, mutable: Mutable<T>) where T: 'static {
+        let id = self.provider();
+
+        PROVIDERS.with(|providers| {
+            providers.borrow_mut().entry(id).or_default().insert(TypeId::of::<T>(), Box::new(mutable));
+        });
+
+        self.callbacks.after_remove(FnDiscard::new(move || {
+            PROVIDERS.with(|providers| {
+                let mut providers = providers.borrow_mut();
+
+                if let Some(values) = providers.get_mut(&id) {
+                    values.remove(&TypeId::of::<T>());
+
+                    if values.is_empty() {
+                        providers.remove(&id);
+                    }
+                }
+            });
+        }));
+    }
+
+    /// Provides a value to all of the descendants of this element.
+    ///
+    /// The descendants can retrieve it with [`DomBuilder::with_context`]. The context is found
+    /// by its type, so you should use a newtype if you want to provide multiple values of the same type.
+    ///
+    /// This works with children which are created later (e.g. with `child_signal` or
+    /// `children_signal_vec`), because the context is looked up when the child is inserted into the DOM.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[derive(Clone)]
+    /// struct User(Rc<UserState>);
+    ///
+    /// html!("div", {
+    ///     .provide_context(User(state))
+    ///     .child(render_profile())
+    /// })
+    /// ```
+    #[inline]
+    pub fn provide_context<T>(mut self, value: T) -> Self where T: Clone + 'static {
+        self.insert_provider(Mutable::new(value));
+        self
+    }
+
+    /// Provides a value to all of the descendants of this element, and updates the value whenever the signal changes.
+    #[inline]
+    pub fn provide_context_signal<T, B>(mut self, value: T, signal: B) -> Self
+        where T: Clone + 'static,
+              B: Signal<Item = T> + 'static {
+
+        let mutable = Mutable::new(value);
+
+        self.insert_provider(mutable.clone());
+
+        self.callbacks.after_remove(for_each(signal, move |value| {
+            mutable.set(value);
+        }));
+
+        self
+    }
+
+    /// Retrieves a value which was provided by one of the ancestors of this element.
+    ///
+    /// The value is `None` until the element is inserted into the DOM.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// fn render_profile() -> Dom {
+    ///     html!("span", {
+    ///         .with_context(|dom, user: ContextValue<User>| {
+    ///             dom.text_signal(user.signal_ref(|user| {
+    ///                 match user {
+    ///                     Some(user) => user.0.name.clone(),
+    ///                     None => "Unknown".to_string(),
+    ///                 }
+    ///             }))
+    ///         })
+    ///     })
+    /// }
+    /// ```
+    #[inline]
+    pub fn with_context<T, F>(mut self, f: F) -> Self
+        where T: Clone + 'static,
+              F: FnOnce(Self, ContextValue<T>) -> Self {
+
+        let context = ContextValue { value: Mutable::new(None) };
+
+        let node: Node = self.element.as_ref().clone();
+        let value = context.value.clone();
+
+        self.callbacks.after_insert(move |callbacks| {
+            if let Some(provider) = find_provider::<T>(&node) {
+                callbacks.after_remove(for_each(provider.signal_cloned(), move |x| {
+                    value.set(Some(x));
+                }));
+            }
+        });
+
+        f(self, context)
+    }
+}
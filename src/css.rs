@@ -0,0 +1,575 @@
+//! Typed CSS values, which can be passed directly to the `style` methods.
+//!
+//! ```rust
+//! use dominator::css::{Px, Percent, Deg, Rgb, Transform};
+//!
+//! html!("div", {
+//!     .style("width", Px(50.0))
+//!     .style("height", Percent(100.0))
+//!     .style("color", Rgb(255, 0, 0))
+//!     .style("transform", Transform::new().translate(Px(10.0), Px(20.0)).rotate(Deg(45.0)))
+//!     .style_signal("left", animation.signal().map(|t| Px(t.range_inclusive(0.0, 100.0))))
+//! })
+//! ```
+//!
+//! The values are formatted into a stack buffer, so they don't allocate a new
+//! `String` every time that they are set.
+use std::fmt;
+use std::fmt::Write;
+use wasm_bindgen::UnwrapThrowExt;
+
+use crate::animation::{Interpolate, Percentage};
+use crate::traits::MultiStr;
+
+
+// Large enough for almost all values, longer values fall back to a String
+const BUFFER_SIZE: usize = 128;
+
+struct Buffer {
+    bytes: [u8; BUFFER_SIZE],
+    len: usize,
+}
+
+impl Buffer {
+    #[inline]
+    fn new() -> Self {
+        Self {
+            bytes: [0; BUFFER_SIZE],
+            len: 0,
+        }
+    }
+
+    #[inline]
+    fn as_str(&self) -> &str {
+        // This is always valid UTF-8, because it is only written to by write_str
+        std::str::from_utf8(&self.bytes[..self.len]).unwrap_throw()
+    }
+}
+
+impl Write for Buffer {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+
+        if end > BUFFER_SIZE {
+            Err(fmt::Error)
+
+        } else {
+            self.bytes[self.len..end].copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+}
+
+fn with_display<A, B, F>(value: &A, f: F) -> B where A: fmt::Display + ?Sized, F: FnOnce(&str) -> B {
+    let mut buffer = Buffer::new();
+
+    if write!(buffer, "{}", value).is_ok() {
+        f(buffer.as_str())
+
+    } else {
+        f(&value.to_string())
+    }
+}
+
+
+fn write_number(f: &mut fmt::Formatter, value: f64, unit: &str) -> fmt::Result {
+    debug_assert!(value.is_finite(), "CSS value must be finite: {}", value);
+    write!(f, "{}{}", value, unit)
+}
+
+fn interpolate_u8(from: u8, to: u8, percentage: Percentage) -> u8 {
+    (from as f64).interpolate(&(to as f64), percentage).round().clamp(0.0, 255.0) as u8
+}
+
+// Interpolates along the shortest path around the color wheel
+fn interpolate_hue(from: f64, to: f64, percentage: Percentage) -> f64 {
+    let mut diff = (to - from) % 360.0;
+
+    if diff > 180.0 {
+        diff -= 360.0;
+
+    } else if diff < -180.0 {
+        diff += 360.0;
+    }
+
+    (from + diff * percentage.into_f64()).rem_euclid(360.0)
+}
+
+macro_rules! css_multi_str {
+    ($name:ident) => {
+        impl MultiStr for $name {
+            #[inline]
+            fn find_map<B, F>(&self, f: F) -> Option<B> where F: FnMut(&str) -> Option<B> {
+                with_display(self, f)
+            }
+        }
+    };
+}
+
+macro_rules! css_unit {
+    ($(#[$attr:meta])* $name:ident, $unit:literal, $kind:ident) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+        pub struct $name(pub f64);
+
+        impl fmt::Display for $name {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write_number(f, self.0, $unit)
+            }
+        }
+
+        impl $kind for $name {}
+
+        impl Interpolate for $name {
+            #[inline]
+            fn interpolate(&self, other: &Self, percentage: Percentage) -> Self {
+                $name(self.0.interpolate(&other.0, percentage))
+            }
+        }
+
+        css_multi_str!($name);
+    };
+}
+
+
+/// A CSS [`<length-percentage>`](https://developer.mozilla.org/en-US/docs/Web/CSS/length-percentage), such as [`Px`] or [`Percent`].
+pub trait Length: fmt::Display {}
+
+/// A CSS [`<angle>`](https://developer.mozilla.org/en-US/docs/Web/CSS/angle), such as [`Deg`] or [`Turn`].
+pub trait Angle: fmt::Display {}
+
+css_unit!(
+    /// A length in pixels, e.g. `Px(5.0)` is `5px`.
+    Px, "px", Length
+);
+
+css_unit!(
+    /// A length relative to the font size of the element, e.g. `Em(1.5)` is `1.5em`.
+    Em, "em", Length
+);
+
+css_unit!(
+    /// A length relative to the font size of the root element, e.g. `Rem(1.5)` is `1.5rem`.
+    Rem, "rem", Length
+);
+
+css_unit!(
+    /// A length relative to the width of the viewport, e.g. `Vw(50.0)` is `50vw`.
+    Vw, "vw", Length
+);
+
+css_unit!(
+    /// A length relative to the height of the viewport, e.g. `Vh(50.0)` is `50vh`.
+    Vh, "vh", Length
+);
+
+css_unit!(
+    /// A percentage, e.g. `Percent(50.0)` is `50%`.
+    Percent, "%", Length
+);
+
+css_unit!(
+    /// An angle in degrees, e.g. `Deg(90.0)` is `90deg`.
+    Deg, "deg", Angle
+);
+
+css_unit!(
+    /// An angle in radians, e.g. `Rad(3.14)` is `3.14rad`.
+    Rad, "rad", Angle
+);
+
+css_unit!(
+    /// An angle in turns, e.g. `Turn(0.5)` is `0.5turn`.
+    Turn, "turn", Angle
+);
+
+
+/// An RGB color, e.g. `Rgb(255, 0, 0)` is `rgb(255, 0, 0)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl fmt::Display for Rgb {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "rgb({}, {}, {})", self.0, self.1, self.2)
+    }
+}
+
+impl Rgb {
+    /// Interpolates in the [`Oklab`] color space, which looks more natural than interpolating in sRGB.
+    #[inline]
+    pub fn interpolate_oklab(&self, other: &Self, percentage: Percentage) -> Self {
+        Oklab::from(*self).interpolate(&Oklab::from(*other), percentage).into()
+    }
+}
+
+/// Interpolates in the sRGB color space.
+impl Interpolate for Rgb {
+    #[inline]
+    fn interpolate(&self, other: &Self, percentage: Percentage) -> Self {
+        Rgb(
+            interpolate_u8(self.0, other.0, percentage),
+            interpolate_u8(self.1, other.1, percentage),
+            interpolate_u8(self.2, other.2, percentage),
+        )
+    }
+}
+
+css_multi_str!(Rgb);
+
+
+/// An RGB color with an alpha channel from `0.0` to `1.0`, e.g. `Rgba(255, 0, 0, 0.5)` is `rgba(255, 0, 0, 0.5)`.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Rgba(pub u8, pub u8, pub u8, pub f64);
+
+impl fmt::Display for Rgba {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        debug_assert!(self.3 >= 0.0 && self.3 <= 1.0, "alpha must be between 0.0 and 1.0: {}", self.3);
+        write!(f, "rgba({}, {}, {}, {})", self.0, self.1, self.2, self.3)
+    }
+}
+
+impl Rgba {
+    /// Interpolates in the [`Oklab`] color space, which looks more natural than interpolating in sRGB.
+    pub fn interpolate_oklab(&self, other: &Self, percentage: Percentage) -> Self {
+        let Rgb(r, g, b) = Rgb(self.0, self.1, self.2).interpolate_oklab(&Rgb(other.0, other.1, other.2), percentage);
+        Rgba(r, g, b, self.3.interpolate(&other.3, percentage).clamp(0.0, 1.0))
+    }
+}
+
+/// Interpolates in the sRGB color space.
+impl Interpolate for Rgba {
+    #[inline]
+    fn interpolate(&self, other: &Self, percentage: Percentage) -> Self {
+        Rgba(
+            interpolate_u8(self.0, other.0, percentage),
+            interpolate_u8(self.1, other.1, percentage),
+            interpolate_u8(self.2, other.2, percentage),
+            self.3.interpolate(&other.3, percentage).clamp(0.0, 1.0),
+        )
+    }
+}
+
+css_multi_str!(Rgba);
+
+
+/// An HSL color, the hue is in degrees and the saturation and lightness are percentages.
+///
+/// e.g. `Hsl(120.0, 100.0, 50.0)` is `hsl(120deg, 100%, 50%)`.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Hsl(pub f64, pub f64, pub f64);
+
+impl fmt::Display for Hsl {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "hsl({}deg, {}%, {}%)", self.0, self.1, self.2)
+    }
+}
+
+impl Interpolate for Hsl {
+    #[inline]
+    fn interpolate(&self, other: &Self, percentage: Percentage) -> Self {
+        Hsl(
+            interpolate_hue(self.0, other.0, percentage),
+            self.1.interpolate(&other.1, percentage),
+            self.2.interpolate(&other.2, percentage),
+        )
+    }
+}
+
+css_multi_str!(Hsl);
+
+
+/// An HSL color with an alpha channel from `0.0` to `1.0`.
+///
+/// e.g. `Hsla(120.0, 100.0, 50.0, 0.5)` is `hsla(120deg, 100%, 50%, 0.5)`.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Hsla(pub f64, pub f64, pub f64, pub f64);
+
+impl fmt::Display for Hsla {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        debug_assert!(self.3 >= 0.0 && self.3 <= 1.0, "alpha must be between 0.0 and 1.0: {}", self.3);
+        write!(f, "hsla({}deg, {}%, {}%, {})", self.0, self.1, self.2, self.3)
+    }
+}
+
+impl Interpolate for Hsla {
+    #[inline]
+    fn interpolate(&self, other: &Self, percentage: Percentage) -> Self {
+        Hsla(
+            interpolate_hue(self.0, other.0, percentage),
+            self.1.interpolate(&other.1, percentage),
+            self.2.interpolate(&other.2, percentage),
+            self.3.interpolate(&other.3, percentage).clamp(0.0, 1.0),
+        )
+    }
+}
+
+css_multi_str!(Hsla);
+
+
+/// A color in the [Oklab](https://bottosson.github.io/posts/oklab/) color space.
+///
+/// Interpolating in Oklab is perceptually uniform, so it avoids the muddy colors of sRGB interpolation.
+///
+/// e.g. `Oklab(0.6, 0.1, -0.1)` is `oklab(0.6 0.1 -0.1)`.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Oklab(pub f64, pub f64, pub f64);
+
+impl fmt::Display for Oklab {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "oklab({} {} {})", self.0, self.1, self.2)
+    }
+}
+
+impl Interpolate for Oklab {
+    #[inline]
+    fn interpolate(&self, other: &Self, percentage: Percentage) -> Self {
+        Oklab(
+            self.0.interpolate(&other.0, percentage),
+            self.1.interpolate(&other.1, percentage),
+            self.2.interpolate(&other.2, percentage),
+        )
+    }
+}
+
+impl From<Rgb> for Oklab {
+    fn from(color: Rgb) -> Self {
+        fn linear(c: u8) -> f64 {
+            let c = c as f64 / 255.0;
+
+            if c <= 0.04045 {
+                c / 12.92
+
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        }
+
+        let r = linear(color.0);
+        let g = linear(color.1);
+        let b = linear(color.2);
+
+        let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+        let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+        let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+
+        Oklab(
+            0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+            1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+            0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+        )
+    }
+}
+
+/// Colors which are outside of the sRGB gamut are clamped.
+impl From<Oklab> for Rgb {
+    fn from(color: Oklab) -> Self {
+        fn gamma(c: f64) -> u8 {
+            let c = if c <= 0.0031308 {
+                12.92 * c
+
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            };
+
+            (c * 255.0).round().clamp(0.0, 255.0) as u8
+        }
+
+        let l = color.0 + 0.3963377774 * color.1 + 0.2158037573 * color.2;
+        let m = color.0 - 0.1055613458 * color.1 - 0.0638541728 * color.2;
+        let s = color.0 - 0.0894841775 * color.1 - 1.2914855480 * color.2;
+
+        let l = l * l * l;
+        let m = m * m * m;
+        let s = s * s * s;
+
+        Rgb(
+            gamma(4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s),
+            gamma(-1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s),
+            gamma(-0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s),
+        )
+    }
+}
+
+css_multi_str!(Oklab);
+
+
+#[doc(hidden)]
+pub trait TransformFunctions {
+    fn write(&self, f: &mut fmt::Formatter) -> Result<bool, fmt::Error>;
+}
+
+impl TransformFunctions for () {
+    #[inline]
+    fn write(&self, _f: &mut fmt::Formatter) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy)]
+pub struct Then<A, B>(A, B);
+
+impl<A, B> TransformFunctions for Then<A, B> where A: TransformFunctions, B: fmt::Display {
+    #[inline]
+    fn write(&self, f: &mut fmt::Formatter) -> Result<bool, fmt::Error> {
+        if self.0.write(f)? {
+            f.write_str(" ")?;
+        }
+
+        write!(f, "{}", self.1)?;
+        Ok(true)
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy)]
+pub struct Function<A> {
+    name: &'static str,
+    args: A,
+}
+
+impl<A, B> Interpolate for Then<A, B> where A: Interpolate, B: Interpolate {
+    #[inline]
+    fn interpolate(&self, other: &Self, percentage: Percentage) -> Self {
+        Then(self.0.interpolate(&other.0, percentage), self.1.interpolate(&other.1, percentage))
+    }
+}
+
+impl<A> Interpolate for Function<A> where A: Interpolate {
+    #[inline]
+    fn interpolate(&self, other: &Self, percentage: Percentage) -> Self {
+        debug_assert_eq!(self.name, other.name);
+
+        Function {
+            name: self.name,
+            args: self.args.interpolate(&other.args, percentage),
+        }
+    }
+}
+
+impl<A> fmt::Display for Function<(A,)> where A: fmt::Display {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}({})", self.name, self.args.0)
+    }
+}
+
+impl<A, B> fmt::Display for Function<(A, B)> where A: fmt::Display, B: fmt::Display {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}({}, {})", self.name, self.args.0, self.args.1)
+    }
+}
+
+/// A CSS [`transform`](https://developer.mozilla.org/en-US/docs/Web/CSS/transform) value.
+///
+/// The transform functions are stored inline, so it doesn't allocate.
+///
+/// # Example
+///
+/// ```rust
+/// Transform::new()
+///     .translate(Px(10.0), Percent(50.0))
+///     .rotate(Deg(45.0))
+///     .scale(1.5)
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Transform<A> {
+    functions: A,
+}
+
+impl Transform<()> {
+    /// Creates an empty transform, which is `none`.
+    #[inline]
+    pub fn new() -> Self {
+        Self { functions: () }
+    }
+}
+
+impl Default for Transform<()> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A> Transform<A> where A: TransformFunctions {
+    #[inline]
+    fn push<B>(self, name: &'static str, args: B) -> Transform<Then<A, Function<B>>> {
+        Transform {
+            functions: Then(self.functions, Function { name, args }),
+        }
+    }
+
+    #[inline]
+    pub fn translate<X, Y>(self, x: X, y: Y) -> Transform<Then<A, Function<(X, Y)>>> where X: Length, Y: Length {
+        self.push("translate", (x, y))
+    }
+
+    #[inline]
+    pub fn translate_x<X>(self, x: X) -> Transform<Then<A, Function<(X,)>>> where X: Length {
+        self.push("translateX", (x,))
+    }
+
+    #[inline]
+    pub fn translate_y<Y>(self, y: Y) -> Transform<Then<A, Function<(Y,)>>> where Y: Length {
+        self.push("translateY", (y,))
+    }
+
+    #[inline]
+    pub fn rotate<B>(self, angle: B) -> Transform<Then<A, Function<(B,)>>> where B: Angle {
+        self.push("rotate", (angle,))
+    }
+
+    #[inline]
+    pub fn scale(self, scale: f64) -> Transform<Then<A, Function<(f64,)>>> {
+        self.push("scale", (scale,))
+    }
+
+    #[inline]
+    pub fn scale_xy(self, x: f64, y: f64) -> Transform<Then<A, Function<(f64, f64)>>> {
+        self.push("scale", (x, y))
+    }
+
+    #[inline]
+    pub fn skew<X, Y>(self, x: X, y: Y) -> Transform<Then<A, Function<(X, Y)>>> where X: Angle, Y: Angle {
+        self.push("skew", (x, y))
+    }
+}
+
+impl<A> fmt::Display for Transform<A> where A: TransformFunctions {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.functions.write(f)? {
+            Ok(())
+
+        } else {
+            f.write_str("none")
+        }
+    }
+}
+
+/// Both transforms must have the same functions in the same order, which is checked by the type system.
+impl<A> Interpolate for Transform<A> where A: Interpolate {
+    #[inline]
+    fn interpolate(&self, other: &Self, percentage: Percentage) -> Self {
+        Transform {
+            functions: self.functions.interpolate(&other.functions, percentage),
+        }
+    }
+}
+
+impl<A> MultiStr for Transform<A> where A: TransformFunctions {
+    #[inline]
+    fn find_map<B, F>(&self, f: F) -> Option<B> where F: FnMut(&str) -> Option<B> {
+        with_display(self, f)
+    }
+}
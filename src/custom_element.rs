@@ -0,0 +1,379 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use discard::Discard;
+use futures_signals::signal::{Signal, Mutable};
+use js_sys::{Array, Reflect};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::{JsCast, intern};
+use web_sys::{HtmlElement, Node, ShadowRoot, ShadowRootInit, ShadowRootMode, CustomEvent, CustomEventInit};
+
+use crate::bindings;
+use crate::dom::{Dom, DomHandle, append_dom};
+use crate::utils::UnwrapJsExt;
+
+
+#[wasm_bindgen(inline_js = r#"
+export function define_custom_element(name, observedAttributes, properties, connected, disconnected, attributeChanged, propertyChanged) {
+    class DominatorElement extends HTMLElement {
+        static get observedAttributes() {
+            return observedAttributes;
+        }
+
+        connectedCallback() {
+            // Properties which were set before the element was upgraded would shadow the accessors
+            for (const property of properties) {
+                if (Object.prototype.hasOwnProperty.call(this, property)) {
+                    const value = this[property];
+                    delete this[property];
+                    this[property] = value;
+                }
+            }
+
+            connected(this);
+        }
+
+        disconnectedCallback() {
+            disconnected(this);
+        }
+
+        attributeChangedCallback(name, oldValue, newValue) {
+            if (oldValue !== newValue) {
+                attributeChanged(this, name, newValue);
+            }
+        }
+    }
+
+    for (const property of properties) {
+        Object.defineProperty(DominatorElement.prototype, property, {
+            configurable: true,
+            enumerable: true,
+            get() {
+                return get_custom_element_property(this, property);
+            },
+            set(value) {
+                if (this.__dominatorProperties === undefined) {
+                    this.__dominatorProperties = {};
+                }
+
+                this.__dominatorProperties[property] = value;
+                propertyChanged(this, property, value);
+            },
+        });
+    }
+
+    customElements.define(name, DominatorElement);
+}
+
+export function is_custom_element_defined(name) {
+    return customElements.get(name) !== undefined;
+}
+
+export function get_custom_element_property(element, name) {
+    const properties = element.__dominatorProperties;
+    return (properties === undefined ? undefined : properties[name]);
+}
+"#)]
+extern "C" {
+    fn define_custom_element(
+        name: &str,
+        observed_attributes: &Array,
+        properties: &Array,
+        connected: &JsValue,
+        disconnected: &JsValue,
+        attribute_changed: &JsValue,
+        property_changed: &JsValue,
+    );
+
+    fn is_custom_element_defined(name: &str) -> bool;
+
+    fn get_custom_element_property(element: &HtmlElement, name: &str) -> JsValue;
+}
+
+
+// The id is stored on the element, so that the instance can be found in the callbacks
+const INSTANCE_ID: &str = "__dominatorCustomElementId";
+
+// Shadow roots can only be attached once, so it is reused when the element is re-connected
+const SHADOW_ROOT: &str = "__dominatorShadowRoot";
+
+thread_local! {
+    static INSTANCE_ID_COUNTER: Cell<u32> = const { Cell::new(0) };
+    static INSTANCES: RefCell<HashMap<u32, Instance>> = RefCell::new(HashMap::new());
+}
+
+struct Instance {
+    context: CustomElementContext,
+    handle: DomHandle,
+}
+
+fn instance_id(element: &HtmlElement) -> u32 {
+    let id = Reflect::get(element, &JsValue::from(intern(INSTANCE_ID))).unwrap_js();
+
+    if let Some(id) = id.as_f64() {
+        id as u32
+
+    } else {
+        let id = INSTANCE_ID_COUNTER.with(|counter| {
+            let id = counter.get();
+            // TODO check for overflow ?
+            counter.set(id + 1);
+            id
+        });
+
+        bindings::set_property(element, intern(INSTANCE_ID), &JsValue::from(id));
+        id
+    }
+}
+
+// The context is cloned so that INSTANCES isn't borrowed while the signals are updated
+fn instance_context(element: &HtmlElement) -> Option<CustomElementContext> {
+    let id = instance_id(element);
+
+    INSTANCES.with(|instances| {
+        instances.borrow().get(&id).map(|instance| instance.context.clone())
+    })
+}
+
+#[track_caller]
+fn shadow_root(element: &HtmlElement, mode: ShadowRootMode) -> ShadowRoot {
+    let shadow = Reflect::get(element, &JsValue::from(intern(SHADOW_ROOT))).unwrap_js();
+
+    if shadow.is_undefined() {
+        let shadow = element.attach_shadow(&ShadowRootInit::new(mode)).unwrap_js();
+        bindings::set_property(element, intern(SHADOW_ROOT), &shadow);
+        shadow
+
+    } else {
+        shadow.unchecked_into()
+    }
+}
+
+
+/// The state of a single custom element, which is passed to the render function of [`CustomElement::define`].
+#[derive(Debug, Clone)]
+pub struct CustomElementContext {
+    element: HtmlElement,
+    attributes: Rc<HashMap<String, Mutable<Option<String>>>>,
+    properties: Rc<HashMap<String, Mutable<JsValue>>>,
+}
+
+impl CustomElementContext {
+    /// The custom element (the host element, not the shadow root).
+    #[inline]
+    pub fn element(&self) -> &HtmlElement {
+        &self.element
+    }
+
+    /// Returns a signal of the attribute's value, it is `None` if the attribute doesn't exist.
+    ///
+    /// # Panics
+    ///
+    /// It panics if the attribute isn't in [`CustomElement::observed_attributes`].
+    #[inline]
+    #[track_caller]
+    pub fn attribute_signal(&self, name: &str) -> impl Signal<Item = Option<String>> {
+        match self.attributes.get(name) {
+            Some(attribute) => attribute.signal_cloned(),
+            None => panic!("Attribute {} is not observed", name),
+        }
+    }
+
+    /// Returns a signal of the property's value, it is `undefined` if the property hasn't been set.
+    ///
+    /// # Panics
+    ///
+    /// It panics if the property isn't in [`CustomElement::properties`].
+    #[inline]
+    #[track_caller]
+    pub fn property_signal(&self, name: &str) -> impl Signal<Item = JsValue> {
+        match self.properties.get(name) {
+            Some(property) => property.signal_cloned(),
+            None => panic!("Property {} is not defined", name),
+        }
+    }
+
+    /// Dispatches a [`CustomEvent`] on the custom element.
+    ///
+    /// The event bubbles, and it is composed so that it can be listened to outside of the shadow root.
+    ///
+    /// Returns `false` if the event was cancelled.
+    #[inline]
+    #[track_caller]
+    pub fn dispatch_event(&self, name: &str, detail: &JsValue) -> bool {
+        let init = CustomEventInit::new();
+        init.set_bubbles(true);
+        init.set_cancelable(true);
+        init.set_composed(true);
+        init.set_detail(detail);
+
+        let event = CustomEvent::new_with_event_init_dict(name, &init).unwrap_js();
+        self.element.dispatch_event(&event).unwrap_js()
+    }
+}
+
+
+/// Defines a [custom element](https://developer.mozilla.org/en-US/docs/Web/API/Web_components/Using_custom_elements)
+/// which is rendered with dominator.
+///
+/// This allows dominator widgets to be used from HTML or other JavaScript frameworks.
+///
+/// The [`Dom`] is created when the element is connected to the document, and it is discarded
+/// when the element is disconnected.
+///
+/// This uses a JavaScript snippet, so it requires a wasm-bindgen target which supports snippets.
+///
+/// # Example
+///
+/// ```rust
+/// CustomElement::new("my-counter")
+///     .observed_attributes(&["label"])
+///     .properties(&["count"])
+///     .shadow_root(ShadowRootMode::Open)
+///     .define(|context| {
+///         html!("button", {
+///             .text_signal(context.attribute_signal("label").map(|label| label.unwrap_or_default()))
+///             .event(clone!(context => move |_: events::Click| {
+///                 context.dispatch_event("increment", &JsValue::UNDEFINED);
+///             }))
+///         })
+///     });
+/// ```
+#[derive(Debug)]
+#[must_use = "The custom element is not defined until the define method is called"]
+pub struct CustomElement {
+    name: String,
+    observed_attributes: Vec<String>,
+    properties: Vec<String>,
+    shadow_root: Option<ShadowRootMode>,
+}
+
+impl CustomElement {
+    /// The name must contain a `-` character.
+    #[inline]
+    #[track_caller]
+    pub fn new(name: &str) -> Self {
+        assert!(name.contains('-'), "Custom element name must contain a -: {}", name);
+
+        Self {
+            name: name.to_owned(),
+            observed_attributes: vec![],
+            properties: vec![],
+            shadow_root: None,
+        }
+    }
+
+    /// The attributes which can be used with [`CustomElementContext::attribute_signal`].
+    #[inline]
+    pub fn observed_attributes(mut self, names: &[&str]) -> Self {
+        self.observed_attributes.extend(names.iter().map(|name| name.to_string()));
+        self
+    }
+
+    /// The JavaScript properties which can be used with [`CustomElementContext::property_signal`].
+    #[inline]
+    pub fn properties(mut self, names: &[&str]) -> Self {
+        self.properties.extend(names.iter().map(|name| name.to_string()));
+        self
+    }
+
+    /// Renders the [`Dom`] inside of a shadow root, instead of as a child of the custom element.
+    #[inline]
+    pub fn shadow_root(mut self, mode: ShadowRootMode) -> Self {
+        self.shadow_root = Some(mode);
+        self
+    }
+
+    /// Registers the custom element with the browser.
+    ///
+    /// The `render` function is called every time that an element is connected to the document.
+    ///
+    /// # Panics
+    ///
+    /// It panics if a custom element with the same name has already been defined,
+    /// because the browser doesn't allow custom elements to be redefined.
+    #[track_caller]
+    pub fn define<F>(self, render: F) where F: Fn(CustomElementContext) -> Dom + 'static {
+        assert!(!is_custom_element_defined(&self.name), "Custom element {} is already defined", self.name);
+
+        let observed_attributes = self.observed_attributes.iter().map(JsValue::from).collect::<Array>();
+        let properties = self.properties.iter().map(JsValue::from).collect::<Array>();
+
+        let attribute_names = self.observed_attributes;
+        let property_names = self.properties;
+        let shadow_mode = self.shadow_root;
+
+        // The closures are Fn because they can be called recursively, e.g. when the render function
+        // creates another element with the same name, so they must not hold any borrows while rendering
+        let connected = Closure::<dyn Fn(HtmlElement)>::new(move |element: HtmlElement| {
+            let attributes = attribute_names.iter().map(|name| {
+                (name.clone(), Mutable::new(element.get_attribute(name)))
+            }).collect();
+
+            let properties = property_names.iter().map(|name| {
+                (name.clone(), Mutable::new(get_custom_element_property(&element, name)))
+            }).collect();
+
+            let context = CustomElementContext {
+                element: element.clone(),
+                attributes: Rc::new(attributes),
+                properties: Rc::new(properties),
+            };
+
+            let parent: Node = match shadow_mode {
+                Some(mode) => shadow_root(&element, mode).into(),
+                None => element.clone().into(),
+            };
+
+            let handle = append_dom(&parent, render(context.clone()));
+
+            let id = instance_id(&element);
+
+            let old = INSTANCES.with(|instances| {
+                instances.borrow_mut().insert(id, Instance { context, handle })
+            });
+
+            if let Some(old) = old {
+                old.handle.discard();
+            }
+        });
+
+        let disconnected = Closure::<dyn Fn(HtmlElement)>::new(move |element: HtmlElement| {
+            let id = instance_id(&element);
+
+            let instance = INSTANCES.with(|instances| instances.borrow_mut().remove(&id));
+
+            if let Some(instance) = instance {
+                instance.handle.discard();
+            }
+        });
+
+        let attribute_changed = Closure::<dyn Fn(HtmlElement, String, Option<String>)>::new(move |element: HtmlElement, name: String, value: Option<String>| {
+            if let Some(context) = instance_context(&element) {
+                if let Some(attribute) = context.attributes.get(&name) {
+                    attribute.set_neq(value);
+                }
+            }
+        });
+
+        let property_changed = Closure::<dyn Fn(HtmlElement, String, JsValue)>::new(move |element: HtmlElement, name: String, value: JsValue| {
+            if let Some(context) = instance_context(&element) {
+                if let Some(property) = context.properties.get(&name) {
+                    property.set(value);
+                }
+            }
+        });
+
+        // The closures are leaked, because custom elements cannot be undefined
+        define_custom_element(
+            &self.name,
+            &observed_attributes,
+            &properties,
+            &connected.into_js_value(),
+            &disconnected.into_js_value(),
+            &attribute_changed.into_js_value(),
+            &property_changed.into_js_value(),
+        );
+    }
+}
@@ -1,4 +1,7 @@
 use std::pin::Pin;
+use std::rc::{Rc, Weak};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::borrow::BorrowMut;
 use std::convert::AsRef;
 use std::future::Future;
@@ -11,7 +14,7 @@ use futures_util::FutureExt;
 use futures_channel::oneshot;
 use discard::{Discard, DiscardOnDrop};
 use wasm_bindgen::{JsValue, UnwrapThrowExt, JsCast, intern};
-use web_sys::{HtmlElement, Node, EventTarget, Element, CssRule, CssStyleRule, CssStyleSheet, CssStyleDeclaration, ShadowRoot, ShadowRootMode, ShadowRootInit, Text};
+use web_sys::{HtmlElement, Node, EventTarget, Element, CssRule, CssStyleRule, CssStyleSheet, CssStyleDeclaration, CssKeyframesRule, CssKeyframeRule, ShadowRoot, ShadowRootMode, ShadowRootInit, Text};
 
 use crate::bindings;
 use crate::bindings::WINDOW;
@@ -20,6 +23,8 @@ use crate::traits::*;
 use crate::fragment::{Fragment, FragmentBuilder};
 use crate::operations;
 use crate::operations::{for_each, spawn_future};
+use crate::animation::{Percentage, AnimatedMapBroadcaster, TransitionMode, Flip, ReducedMotion};
+use crate::theme::Theme;
 use crate::utils::{EventListener, on, RefCounter, MutableListener, UnwrapJsExt, ValueDiscard, FnDiscard};
 
 #[cfg(doc)]
@@ -431,7 +436,7 @@ fn set_option<A, B, C, D, F>(element: A, callbacks: &mut Callbacks, value: D, mu
 
 // TODO should this inline ?
 // TODO track_caller
-fn set_style<A, B>(style: &CssStyleDeclaration, name: &A, value: B, important: bool)
+fn set_style<A, B>(style: &CssStyleDeclaration, name: &A, value: B, important: bool, mut extracted: Option<&mut String>)
     where A: MultiStr,
           B: MultiStr {
 
@@ -464,7 +469,15 @@ fn set_style<A, B>(style: &CssStyleDeclaration, name: &A, value: B, important: b
 
         value.find_map(|value| {
             // TODO should this intern ?
-            try_set_style(style, &mut names, &mut values, &name, &value, important)
+            let okay = try_set_style(style, &mut names, &mut values, &name, &value, important);
+
+            if okay.is_some() {
+                if let Some(extracted) = extracted.as_deref_mut() {
+                    push_declaration(extracted, name, value, important);
+                }
+            }
+
+            okay
         })
     });
 
@@ -476,6 +489,45 @@ fn set_style<A, B>(style: &CssStyleDeclaration, name: &A, value: B, important: b
     }
 }
 
+// Replaces every `.class_name` in the selector, so that identical classes have identical selectors
+fn replace_class_name(selector: &str, class_name: &str) -> String {
+    let pattern = format!(".{}", class_name);
+
+    let mut output = String::with_capacity(selector.len());
+    let mut rest = selector;
+
+    while let Some(index) = rest.find(&pattern) {
+        let after = &rest[(index + pattern.len())..];
+
+        output.push_str(&rest[..index]);
+
+        // This makes sure that `.foo_1` doesn't match `.foo_12`
+        if after.starts_with(|c: char| c.is_alphanumeric() || c == '_' || c == '-') {
+            output.push_str(&pattern);
+
+        } else {
+            output.push('&');
+        }
+
+        rest = after;
+    }
+
+    output.push_str(rest);
+    output
+}
+
+fn push_declaration(extracted: &mut String, name: &str, value: &str, important: bool) {
+    extracted.push_str(name);
+    extracted.push_str(": ");
+    extracted.push_str(value);
+
+    if important {
+        extracted.push_str(" !important");
+    }
+
+    extracted.push_str("; ");
+}
+
 // TODO should this inline ?
 // TODO track_caller
 fn set_style_signal<A, B, C, D>(style: CssStyleDeclaration, callbacks: &mut Callbacks, name: A, value: D, important: bool)
@@ -488,7 +540,7 @@ fn set_style_signal<A, B, C, D>(style: CssStyleDeclaration, callbacks: &mut Call
         match value {
             Some(value) => {
                 // TODO should this intern or not ?
-                set_style(style, &name, value, important);
+                set_style(style, &name, value, important, None);
             },
             None => {
                 name.each(|name| {
@@ -528,6 +580,50 @@ fn set_style_unchecked_signal<A, B, C, D>(style: CssStyleDeclaration, callbacks:
     });
 }
 
+#[track_caller]
+fn assert_css_var<A>(name: &A) where A: AsStr {
+    name.with_str(|name| {
+        assert!(name.starts_with("--"), "CSS variable must start with --: {}", name);
+    });
+}
+
+#[track_caller]
+fn set_theme<A>(style: &CssStyleDeclaration, theme: &A, mut names: Option<&mut Vec<String>>) where A: Theme {
+    theme.each_var(|name, value| {
+        assert_css_var(&name);
+
+        bindings::set_style(style, intern(name), value, false);
+
+        if let Some(names) = names.as_deref_mut() {
+            names.push(String::from(name));
+        }
+    });
+}
+
+// TODO should this inline ?
+// TODO track_caller
+fn set_theme_signal<A, B>(style: CssStyleDeclaration, callbacks: &mut Callbacks, theme: B)
+    where A: Theme,
+          B: Signal<Item = A> + 'static {
+
+    let mut old_names: Vec<String> = vec![];
+
+    callbacks.after_remove(for_each(theme, move |theme| {
+        let mut new_names = vec![];
+
+        set_theme(&style, &theme, Some(&mut new_names));
+
+        // The new theme might not have all of the variables of the old theme
+        for name in old_names.drain(..) {
+            if !new_names.contains(&name) {
+                bindings::remove_style(&style, &name);
+            }
+        }
+
+        old_names = new_names;
+    }));
+}
+
 // TODO check that the property *actually* was changed ?
 // TODO maybe use AsRef<Object> ?
 // TODO should this inline ?
@@ -585,12 +681,79 @@ impl Default for EventOptions {
 }
 
 
+/// A reference to a DOM node, which can be used outside of the [`DomBuilder`].
+///
+/// Use [`DomBuilder::node_ref`] to set it. It is `None` until the node is created,
+/// and it becomes `None` again when the node is removed from the DOM.
+///
+/// Cloning a `NodeRef` is cheap, and all of the clones refer to the same node.
+#[derive(Debug)]
+pub struct NodeRef<A> {
+    node: Mutable<Option<A>>,
+}
+
+impl<A> NodeRef<A> {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            node: Mutable::new(None),
+        }
+    }
+
+    /// Calls `f` with the node, or returns `None` if the node doesn't exist.
+    #[inline]
+    pub fn with<B, F>(&self, f: F) -> Option<B> where F: FnOnce(&A) -> B {
+        self.node.lock_ref().as_ref().map(f)
+    }
+
+    /// Whether the node currently exists.
+    #[inline]
+    pub fn is_some(&self) -> bool {
+        self.node.lock_ref().is_some()
+    }
+
+    /// Returns a signal which is `true` when the node exists.
+    #[inline]
+    pub fn is_some_signal(&self) -> impl Signal<Item = bool> {
+        self.node.signal_ref(|node| node.is_some())
+    }
+}
+
+impl<A> NodeRef<A> where A: Clone {
+    #[inline]
+    pub fn get(&self) -> Option<A> {
+        self.node.get_cloned()
+    }
+
+    #[inline]
+    pub fn signal(&self) -> impl Signal<Item = Option<A>> {
+        self.node.signal_cloned()
+    }
+}
+
+impl<A> Clone for NodeRef<A> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            node: self.node.clone(),
+        }
+    }
+}
+
+impl<A> Default for NodeRef<A> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
 // TODO better warning message for must_use
 #[must_use]
 #[derive(Debug)]
 pub struct DomBuilder<A> {
-    element: A,
-    callbacks: Callbacks,
+    pub(crate) element: A,
+    pub(crate) callbacks: Callbacks,
 }
 
 impl<A> DomBuilder<A> where A: JsCast {
@@ -725,6 +888,50 @@ impl<A> DomBuilder<A> where A: Clone + 'static {
     }
 }
 
+impl<A> DomBuilder<A> where A: Clone + PartialEq + 'static {
+    /// Stores the element in the [`NodeRef`].
+    ///
+    /// The [`NodeRef`] is set immediately, and it is cleared when the element is removed from the DOM.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let input = NodeRef::new();
+    ///
+    /// html!("div", {
+    ///     .child(html!("input" => HtmlInputElement, {
+    ///         .node_ref(&input)
+    ///     }))
+    ///
+    ///     .child(html!("button", {
+    ///         .event(clone!(input => move |_: events::Click| {
+    ///             input.with(|input| {
+    ///                 input.focus().unwrap();
+    ///             });
+    ///         }))
+    ///     }))
+    /// })
+    /// ```
+    #[inline]
+    pub fn node_ref(mut self, node_ref: &NodeRef<A>) -> Self {
+        node_ref.node.set(Some(self.element.clone()));
+
+        let node_ref = node_ref.clone();
+        let element = self.element.clone();
+
+        self.callbacks.after_remove(FnDiscard::new(move || {
+            let mut lock = node_ref.node.lock_mut();
+
+            // The NodeRef might have been given to a different element in the meantime
+            if lock.as_ref() == Some(&element) {
+                *lock = None;
+            }
+        }));
+
+        self
+    }
+}
+
 impl<A> DomBuilder<A> where A: Into<Node> {
     #[inline]
     pub fn into_dom(self) -> Dom {
@@ -874,6 +1081,37 @@ impl<A> DomBuilder<A> where A: AsRef<Node> {
         self
     }
 
+    /// The same as [`child_signal`](DomBuilder::child_signal), except the children are animated when they change.
+    ///
+    /// The callback receives the signal's value and an animation, and it returns the child [`Dom`].
+    ///
+    /// The animation goes from `0` to `1` when the child is inserted, and from `1` to `0` when the child is
+    /// being removed. The old child stays in the DOM until its animation is finished.
+    ///
+    /// The first child isn't animated, it starts at `1`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// html!("main", {
+    ///     .child_signal_animated(300.0, TransitionMode::Sequential, route.signal().map(Some), |route, animation| {
+    ///         html!("div", {
+    ///             .style_signal("opacity", animation.signal().map(|t| t.into_f64().to_string()))
+    ///             .child(render_route(route))
+    ///         })
+    ///     })
+    /// })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn child_signal_animated<B, C, F>(mut self, duration: f64, mode: TransitionMode, signal: C, f: F) -> Self
+        where C: Signal<Item = Option<B>> + 'static,
+              F: FnMut(B, AnimatedMapBroadcaster) -> Dom + 'static {
+
+        operations::insert_child_signal_animated(self.element.as_ref().clone(), &mut self.callbacks, duration, mode, signal, f);
+        self
+    }
+
     // TODO figure out how to make this owned rather than &mut
     #[inline]
     #[track_caller]
@@ -890,6 +1128,52 @@ impl<A> DomBuilder<A> where A: AsRef<Node> {
         operations::insert_children_signal_vec(self.element.as_ref().clone(), &mut self.callbacks, children);
         self
     }
+
+    /// The same as [`children_signal_vec`](DomBuilder::children_signal_vec), except the children
+    /// smoothly slide to their new position when they move.
+    ///
+    /// This uses the [FLIP](https://aerotwist.com/blog/flip-your-animations/) technique: the positions of the
+    /// children are measured before and after every change, and then the `transform` style is animated
+    /// from the old position to the new position over `duration` milliseconds.
+    ///
+    /// This animates the children when they are moved, and also when other children are inserted or removed.
+    /// When all of the children are replaced (e.g. by sorting with [`replace_cloned`](futures_signals::signal_vec::MutableVecLockMut::replace_cloned)),
+    /// the elements which are in both the old and new children are animated from their old position.
+    /// It works well with [`animated_map`](crate::animation::AnimatedSignalVec::animated_map), because the
+    /// other children will slide into place after a child's removal animation has finished.
+    ///
+    /// Only HTML elements are animated, and the children's inline `transform` style is overwritten while they are moving.
+    #[inline]
+    #[track_caller]
+    pub fn children_signal_vec_flip<B>(mut self, duration: f64, children: B) -> Self
+        where B: SignalVec<Item = Dom> + 'static {
+
+        operations::insert_children_signal_vec_with(self.element.as_ref().clone(), &mut self.callbacks, children, Some(Flip::new(duration, None)));
+        self
+    }
+
+    /// The same as [`children_signal_vec_flip`](DomBuilder::children_signal_vec_flip), except it uses
+    /// the [`ReducedMotion`](crate::animation::ReducedMotion) policy instead of the global policy.
+    #[inline]
+    #[track_caller]
+    pub fn children_signal_vec_flip_reduced_motion<B>(mut self, duration: f64, reduced_motion: ReducedMotion, children: B) -> Self
+        where B: SignalVec<Item = Dom> + 'static {
+
+        operations::insert_children_signal_vec_with(self.element.as_ref().clone(), &mut self.callbacks, children, Some(Flip::new(duration, Some(reduced_motion))));
+        self
+    }
+}
+
+impl DomBuilder<ShadowRoot> {
+    /// Adopts the [`AdoptedStylesheet`] into the shadow root, so its styles apply inside of the shadow root.
+    ///
+    /// Adopting the same stylesheet multiple times into the same shadow root does nothing.
+    #[inline]
+    #[track_caller]
+    pub fn adopt_stylesheet(self, sheet: &AdoptedStylesheet) -> Self {
+        bindings::adopt_stylesheet(&self.element, &sheet.sheet);
+        self
+    }
 }
 
 impl<A> DomBuilder<A> where A: AsRef<Element> {
@@ -1123,6 +1407,57 @@ impl<A> DomBuilder<A> where A: AsRef<Element> {
     }
 
 
+    /// Adds a [`DynamicClass`] to the element.
+    ///
+    /// The class is kept alive until the element is removed from the DOM.
+    #[inline]
+    #[track_caller]
+    pub fn dynamic_class(mut self, class: &DynamicClass) -> Self {
+        bindings::add_class(&self.element.as_ref().class_list(), class.class_name());
+        self.callbacks.after_remove(ValueDiscard::new(class.clone()));
+        self
+    }
+
+    // TODO should this inline ?
+    // TODO track_caller
+    fn set_dynamic_class_signal<B>(&mut self, value: B)
+        where B: Signal<Item = Option<DynamicClass>> + 'static {
+
+        let element = self.element.as_ref().class_list();
+
+        let mut current: Option<DynamicClass> = None;
+
+        self.callbacks.after_remove(for_each(value, move |value| {
+            if current != value {
+                if let Some(old) = &current {
+                    bindings::remove_class(&element, old.class_name());
+                }
+
+                if let Some(new) = &value {
+                    bindings::add_class(&element, new.class_name());
+                }
+
+                // This drops the old class, which might remove it from the stylesheet
+                current = value;
+            }
+        }));
+    }
+
+    /// Adds a [`DynamicClass`] to the element, and replaces it whenever the `Signal` changes.
+    ///
+    /// If the `Signal` is `None` then the class is removed.
+    ///
+    /// The current class is kept alive until it is replaced or the element is removed from the DOM.
+    #[inline]
+    #[track_caller]
+    pub fn dynamic_class_signal<B>(mut self, value: B) -> Self
+        where B: Signal<Item = Option<DynamicClass>> + 'static {
+
+        self.set_dynamic_class_signal(value);
+        self
+    }
+
+
     // TODO use OptionStr ?
     // TODO should this inline ?
     // TODO track_caller
@@ -1167,7 +1502,7 @@ impl<A> DomBuilder<A> where A: AsRef<HtmlElement> {
     pub fn style<B, C>(self, name: B, value: C) -> Self
         where B: MultiStr,
               C: MultiStr {
-        set_style(&self.element.as_ref().style(), &name, value, false);
+        set_style(&self.element.as_ref().style(), &name, value, false, None);
         self
     }
 
@@ -1176,7 +1511,7 @@ impl<A> DomBuilder<A> where A: AsRef<HtmlElement> {
     pub fn style_important<B, C>(self, name: B, value: C) -> Self
         where B: MultiStr,
               C: MultiStr {
-        set_style(&self.element.as_ref().style(), &name, value, true);
+        set_style(&self.element.as_ref().style(), &name, value, true, None);
         self
     }
 
@@ -1231,6 +1566,60 @@ impl<A> DomBuilder<A> where A: AsRef<HtmlElement> {
         self
     }
 
+    /// Sets a CSS custom property (variable) on the element.
+    ///
+    /// The name must start with `--`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// html!("div", {
+    ///     .css_var("--accent", "green")
+    /// })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn css_var<B, C>(self, name: B, value: C) -> Self
+        where B: AsStr,
+              C: AsStr {
+        assert_css_var(&name);
+        self.style_unchecked(name, value)
+    }
+
+    /// Sets a CSS custom property (variable) on the element, and updates it whenever the signal changes.
+    ///
+    /// If the signal's value is `None` then the custom property is removed.
+    #[inline]
+    #[track_caller]
+    pub fn css_var_signal<B, C, D, E>(self, name: B, value: E) -> Self
+        where B: AsStr + 'static,
+              C: AsStr,
+              D: OptionStr<Output = C>,
+              E: Signal<Item = D> + 'static {
+        assert_css_var(&name);
+        self.style_unchecked_signal(name, value)
+    }
+
+    /// Sets all of the [`Theme`]'s custom properties on the element.
+    #[inline]
+    #[track_caller]
+    pub fn theme<B>(self, theme: B) -> Self where B: Theme {
+        set_theme(&self.element.as_ref().style(), &theme, None);
+        self
+    }
+
+    /// Sets all of the [`Theme`]'s custom properties on the element, and switches the theme whenever the signal changes.
+    ///
+    /// Any custom properties which were set by the old theme but not by the new theme are removed.
+    #[inline]
+    #[track_caller]
+    pub fn theme_signal<B, C>(mut self, theme: C) -> Self
+        where B: Theme,
+              C: Signal<Item = B> + 'static {
+        set_theme_signal(self.element.as_ref().style(), &mut self.callbacks, theme);
+        self
+    }
+
 
     // TODO remove the `value` argument ?
     #[inline]
@@ -1318,62 +1707,508 @@ impl<A> DomBuilder<A> where A: AsRef<HtmlElement> {
 #[inline]
 pub fn stylesheet_raw<A>(css: A) where A: AsStr {
     css.with_str(|css| {
-        bindings::create_stylesheet(Some(css));
+        if let Some(stylesheet) = custom_stylesheet() {
+            bindings::append_stylesheet(&stylesheet, css);
+
+        } else {
+            STYLE_NONCE.with(|nonce| {
+                bindings::create_stylesheet(Some(css), nonce.borrow().as_deref());
+            });
+        }
     });
 }
 
 
-// TODO better warning message for must_use
-#[must_use]
-pub struct StylesheetBuilder {
-    element: CssStyleDeclaration,
-    callbacks: Callbacks,
+thread_local! {
+    static STYLE_NONCE: RefCell<Option<String>> = const { RefCell::new(None) };
+    static STYLESHEET: RefCell<Option<CssStyleSheet>> = const { RefCell::new(None) };
+    // Whether STYLESHEET was set with set_stylesheet, rather than being created by dominator
+    static IS_CUSTOM_STYLESHEET: Cell<bool> = const { Cell::new(false) };
 }
 
-// TODO remove the CssStyleRule when this is discarded
-impl StylesheetBuilder {
-    fn __internal_rules<A>(rules: &A) -> CssRule where A: MultiStr {
-        // TODO can this be made faster ?
-        // TODO somehow share this safely between threads ?
-        thread_local! {
-            static STYLESHEET: CssStyleSheet = bindings::create_stylesheet(None);
-        }
+// Returns the stylesheet which was set with set_stylesheet
+fn custom_stylesheet() -> Option<CssStyleSheet> {
+    if IS_CUSTOM_STYLESHEET.with(|is_custom| is_custom.get()) {
+        STYLESHEET.with(|stylesheet| stylesheet.borrow().clone())
 
-        STYLESHEET.with(move |stylesheet| {
-            let mut failed = vec![];
+    } else {
+        None
+    }
+}
 
-            let okay = rules.find_map(|rule| {
-                // TODO maybe intern the rule ?
-                if let Ok(declaration) = bindings::make_rule(stylesheet, rule) {
-                    Some(declaration)
+/// Sets the [nonce](https://developer.mozilla.org/en-US/docs/Web/HTML/Global_attributes/nonce)
+/// for the `<style>` elements which are created by dominator.
+///
+/// This allows [`class!`](crate::class), [`stylesheet!`](crate::stylesheet), and [`stylesheet_raw`]
+/// to work with a Content-Security-Policy which doesn't allow `'unsafe-inline'` styles.
+///
+/// This must be called before any styles are created, usually at the start of `main`.
+///
+/// # Example
+///
+/// ```rust
+/// // The nonce is usually sent by the server, e.g. in a <meta> tag
+/// set_style_nonce("2726c7f26c");
+/// ```
+#[inline]
+pub fn set_style_nonce<A>(nonce: A) where A: AsStr {
+    nonce.with_str(|nonce| {
+        STYLE_NONCE.with(|style_nonce| {
+            *style_nonce.borrow_mut() = Some(String::from(nonce));
+        });
+    });
+}
 
-                } else {
-                    failed.push(String::from(rule));
-                    None
-                }
-            });
+/// Uses an existing stylesheet for all of the styles which are created by dominator,
+/// instead of creating new `<style>` elements.
+///
+/// This is useful with a strict Content-Security-Policy, because you can put a `<style>`
+/// element with a nonce into the HTML and then pass in its `sheet`.
+///
+/// This affects [`class!`](crate::class), [`stylesheet!`](crate::stylesheet), [`stylesheet_raw`],
+/// and the other style macros.
+///
+/// [`stylesheet_raw`] appends its rules to the end of the stylesheet, so it cannot contain
+/// `@import` or `@namespace` rules.
+///
+/// This must be called before any styles are created, usually at the start of `main`.
+#[inline]
+pub fn set_stylesheet(stylesheet: CssStyleSheet) {
+    STYLESHEET.with(|global| {
+        *global.borrow_mut() = Some(stylesheet);
+    });
 
-            if let Some(okay) = okay {
-                okay
+    IS_CUSTOM_STYLESHEET.with(|is_custom| is_custom.set(true));
+}
 
-            } else {
-                // TODO maybe make this configurable
-                panic!("selectors are incorrect:\n  {}", failed.join("\n  "));
-            }
-        })
-    }
 
-    #[doc(hidden)]
+/// Controls how the static styles of [`stylesheet!`](crate::stylesheet) and [`class!`](crate::class) are inserted.
+///
+/// Static styles are the styles which don't use signals. By default they are
+/// inserted into the DOM at runtime, but it is also possible to extract them
+/// into a `.css` file which is served alongside the app:
+///
+/// 1. Build the app with [`StaticCss::Extract`], run it (e.g. during server-side
+///    rendering or in a headless browser), and then write the result of
+///    [`extracted_css`] into a `.css` file.
+///
+/// 2. Link that `.css` file in the page and build the app with [`StaticCss::Precomputed`].
+///    Now the static styles won't be inserted at runtime.
+///
+/// Both builds must use the same source code, because the class names are generated
+/// from the module path, the prefix, and the line and column of the [`class!`](crate::class) macro.
+/// They don't depend on the file path, so the builds can happen on different machines.
+///
+/// The dynamic styles (the `_signal` methods) are always inserted at runtime.
+/// [`dynamic_class!`](crate::dynamic_class), [`keyframes!`](crate::keyframes),
+/// [`font_face!`](crate::font_face), and [`custom_property!`](crate::custom_property)
+/// are also always inserted at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum StaticCss {
+    /// Inserts the static styles at runtime. This is the default.
+    #[default]
+    Runtime,
+
+    /// Inserts the static styles at runtime, and also records them so they can be retrieved with [`extracted_css`].
+    Extract,
+
+    /// Assumes that the static styles have already been loaded, so they are not inserted at runtime.
+    Precomputed,
+}
+
+thread_local! {
+    static STATIC_CSS: Cell<StaticCss> = const { Cell::new(StaticCss::Runtime) };
+    static EXTRACTED_CSS: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Sets the [`StaticCss`] mode.
+///
+/// This must be called before any [`stylesheet!`](crate::stylesheet) or [`class!`](crate::class)
+/// is created, usually at the start of `main`.
+#[inline]
+pub fn set_static_css(mode: StaticCss) {
+    STATIC_CSS.with(|static_css| static_css.set(mode));
+}
+
+/// Returns all of the static CSS which has been recorded so far.
+///
+/// This only records CSS when the mode is [`StaticCss::Extract`].
+#[inline]
+pub fn extracted_css() -> String {
+    EXTRACTED_CSS.with(|css| css.borrow().clone())
+}
+
+
+thread_local! {
+    static ADOPTED_TARGETS: RefCell<Vec<CssStyleSheet>> = const { RefCell::new(vec![]) };
+}
+
+fn is_adopting() -> bool {
+    ADOPTED_TARGETS.with(|targets| !targets.borrow().is_empty())
+}
+
+/// A stylesheet which can be shared between many shadow roots.
+///
+/// Normally [`class!`](crate::class) and [`stylesheet!`](crate::stylesheet) insert their
+/// styles into the document, so they don't apply inside of shadow roots. Inside of
+/// [`AdoptedStylesheet::with`] they insert their styles into this stylesheet instead,
+/// which can then be adopted by any number of shadow roots with
+/// [`DomBuilder::adopt_stylesheet`].
+///
+/// The stylesheet is shared by reference, so adopting it into many shadow roots doesn't duplicate the styles.
+///
+/// # Example
+///
+/// ```rust
+/// let sheet = AdoptedStylesheet::new();
+///
+/// let class = sheet.with(|| class! {
+///     .style("color", "green")
+/// });
+///
+/// html!("div", {
+///     .shadow_root!(ShadowRootMode::Open => {
+///         .adopt_stylesheet(&sheet)
+///
+///         .child(html!("span", {
+///             .class(&class)
+///         }))
+///     })
+/// })
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdoptedStylesheet {
+    sheet: CssStyleSheet,
+}
+
+impl AdoptedStylesheet {
     #[inline]
-    pub fn __internal_stylesheet<A>(rules: A) -> Self where A: MultiStr {
-        let element = Self::__internal_rules(&rules).unchecked_into::<CssStyleRule>();
+    #[track_caller]
+    pub fn new() -> Self {
+        Self {
+            sheet: bindings::create_constructable_stylesheet(),
+        }
+    }
+
+    /// Calls `f`, and any [`class!`](crate::class), [`stylesheet!`](crate::stylesheet),
+    /// [`dynamic_class!`](crate::dynamic_class) or [`keyframes!`](crate::keyframes)
+    /// which are created inside of `f` will insert their styles into this stylesheet.
+    ///
+    /// The static styles are always inserted at runtime, regardless of [`StaticCss`].
+    ///
+    /// [`font_face!`](crate::font_face) and [`custom_property!`](crate::custom_property) are
+    /// ignored by browsers inside of shadow roots, so they are always inserted into the document.
+    #[inline]
+    pub fn with<A, F>(&self, f: F) -> A where F: FnOnce() -> A {
+        struct Pop;
+
+        impl Drop for Pop {
+            #[inline]
+            fn drop(&mut self) {
+                ADOPTED_TARGETS.with(|targets| {
+                    targets.borrow_mut().pop();
+                });
+            }
+        }
+
+        ADOPTED_TARGETS.with(|targets| {
+            targets.borrow_mut().push(self.sheet.clone());
+        });
+
+        // This makes sure that it is popped even if `f` panics
+        let _pop = Pop;
+
+        f()
+    }
+
+    /// Returns the underlying `CSSStyleSheet`.
+    #[inline]
+    pub fn as_css_style_sheet(&self) -> &CssStyleSheet {
+        &self.sheet
+    }
+}
+
+impl Default for AdoptedStylesheet {
+    #[inline]
+    #[track_caller]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 
+// TODO better warning message for must_use
+#[must_use]
+pub struct StylesheetBuilder {
+    // This is `None` if the styles are precomputed and the rule hasn't been created yet
+    rule: Option<StylesheetRule>,
+    // This is used to lazily create the rule when the styles are precomputed
+    selectors: Vec<String>,
+    is_precomputed: bool,
+    // This accumulates the static styles when the styles are being extracted
+    extracted: Option<String>,
+    // This is `Some` for a dynamic class which hasn't created its rule yet, the styles are applied when the rule is created
+    pending: Option<Vec<PendingStyle>>,
+    callbacks: Callbacks,
+}
+
+// A static style of a dynamic class, it keeps all of the choices so that the browser can pick one when the rule is created
+enum PendingStyle {
+    Style {
+        names: Vec<String>,
+        values: Vec<String>,
+        important: bool,
+    },
+    Unchecked {
+        name: String,
+        value: String,
+    },
+    Raw(String),
+}
+
+impl PendingStyle {
+    fn strings<A>(input: &A) -> Vec<String> where A: MultiStr {
+        let mut output = vec![];
+        input.each(|x| output.push(String::from(x)));
+        output
+    }
+
+    fn apply(&self, element: &CssStyleDeclaration) {
+        match self {
+            PendingStyle::Style { names, values, important } => {
+                set_style(element, &Strings(names), Strings(values), *important, None);
+            },
+            PendingStyle::Unchecked { name, value } => {
+                bindings::set_style(element, intern(name), value, false);
+            },
+            PendingStyle::Raw(css) => {
+                bindings::append_raw(element, css);
+            },
+        }
+    }
+
+    fn push_key(&self, key: &mut String) {
+        match self {
+            PendingStyle::Style { names, values, important } => {
+                push_declaration(key, &names.join(" | "), &values.join(" | "), *important);
+            },
+            PendingStyle::Unchecked { name, value } => {
+                push_declaration(key, name, value, false);
+            },
+            PendingStyle::Raw(css) => {
+                key.push_str(css.trim());
+                key.push(' ');
+            },
+        }
+    }
+}
+
+struct StylesheetRule {
+    rule: CssRule,
+    element: CssStyleDeclaration,
+}
+
+impl StylesheetRule {
+    fn new(rule: CssRule) -> Self {
         Self {
-            element: element.style(),
-            callbacks: Callbacks::new(),
+            element: rule.unchecked_ref::<CssStyleRule>().style(),
+            rule,
+        }
+    }
+}
+
+struct Strings<'a>(&'a [String]);
+
+impl<'a> MultiStr for Strings<'a> {
+    #[inline]
+    fn find_map<B, F>(&self, mut f: F) -> Option<B> where F: FnMut(&str) -> Option<B> {
+        self.0.iter().find_map(|x| f(x))
+    }
+}
+
+// TODO remove the CssStyleRule when this is discarded
+impl StylesheetBuilder {
+    fn insert_rules<A>(stylesheet: &CssStyleSheet, rules: &A) -> CssRule where A: MultiStr {
+        let mut failed = vec![];
+
+        let okay = rules.find_map(|rule| {
+            // TODO maybe intern the rule ?
+            if let Ok(declaration) = bindings::make_rule(stylesheet, rule) {
+                Some(declaration)
+
+            } else {
+                failed.push(String::from(rule));
+                None
+            }
+        });
+
+        if let Some(okay) = okay {
+            okay
+
+        } else {
+            // TODO maybe make this configurable
+            panic!("selectors are incorrect:\n  {}", failed.join("\n  "));
+        }
+    }
+
+    /// Inserts the rules into the document's stylesheet, ignoring [`AdoptedStylesheet::with`].
+    fn document_rules<A>(rules: &A) -> CssRule where A: MultiStr {
+        // TODO can this be made faster ?
+        // TODO somehow share this safely between threads ?
+        let stylesheet = STYLESHEET.with(|stylesheet| {
+            stylesheet.borrow_mut().get_or_insert_with(|| {
+                STYLE_NONCE.with(|nonce| bindings::create_stylesheet(None, nonce.borrow().as_deref()))
+            }).clone()
+        });
+
+        Self::insert_rules(&stylesheet, rules)
+    }
+
+    fn __internal_rules<A>(rules: &A) -> CssRule where A: MultiStr {
+        let target = ADOPTED_TARGETS.with(|targets| targets.borrow().last().cloned());
+
+        if let Some(target) = target {
+            Self::insert_rules(&target, rules)
+
+        } else {
+            Self::document_rules(rules)
+        }
+    }
+
+    fn new_rule<A>(rules: A, is_static: bool) -> Self where A: MultiStr {
+        // Adopted stylesheets cannot be extracted, because the extracted CSS is loaded into the document
+        let mode = if is_static && !is_adopting() {
+            STATIC_CSS.with(|mode| mode.get())
+
+        } else {
+            StaticCss::Runtime
+        };
+
+        if let StaticCss::Precomputed = mode {
+            let mut selectors = vec![];
+
+            rules.each(|rule| {
+                selectors.push(String::from(rule));
+            });
+
+            Self {
+                rule: None,
+                selectors,
+                is_precomputed: true,
+                extracted: None,
+                pending: None,
+                callbacks: Callbacks::new(),
+            }
+
+        } else {
+            Self {
+                rule: Some(StylesheetRule::new(Self::__internal_rules(&rules))),
+                selectors: vec![],
+                is_precomputed: false,
+                extracted: if let StaticCss::Extract = mode { Some(String::new()) } else { None },
+                pending: None,
+                callbacks: Callbacks::new(),
+            }
+        }
+    }
+
+    fn new_dynamic<A>(rules: A) -> Self where A: MultiStr {
+        let rules = MapMultiStr::new(rules, |rule| format!("{} {{}}", rule));
+
+        // Classes inside of adopted stylesheets aren't deduplicated, so they don't need to wait
+        if is_adopting() {
+            Self::new_rule(rules, false)
+
+        } else {
+            // The rule isn't created until it is known that there isn't an identical class
+            Self {
+                rule: None,
+                selectors: PendingStyle::strings(&rules),
+                is_precomputed: false,
+                extracted: None,
+                pending: Some(vec![]),
+                callbacks: Callbacks::new(),
+            }
+        }
+    }
+
+    /// Returns the style declaration, creating the rule if it doesn't exist yet.
+    fn element(&mut self) -> &CssStyleDeclaration {
+        let selectors = &self.selectors;
+        let pending = &mut self.pending;
+
+        &self.rule.get_or_insert_with(|| {
+            let rule = StylesheetRule::new(Self::__internal_rules(&Strings(selectors)));
+
+            if let Some(pending) = pending.take() {
+                for style in pending.iter() {
+                    style.apply(&rule.element);
+                }
+            }
+
+            rule
+        }).element
+    }
+
+    // Returns the key which is used to deduplicate dynamic classes, it is `None` if the rule has already been created
+    fn pending_key(&self, class_name: &str) -> Option<String> {
+        self.pending.as_ref().map(|pending| {
+            let mut key = self.selectors.iter()
+                .map(|selector| replace_class_name(selector, class_name))
+                .collect::<Vec<String>>()
+                .join(" | ");
+
+            key.push_str(" { ");
+
+            for style in pending.iter() {
+                style.push_key(&mut key);
+            }
+
+            key.push('}');
+            key
+        })
+    }
+
+    #[track_caller]
+    fn set_style_unchecked(&mut self, name: &str, value: &str) {
+        if let Some(pending) = &mut self.pending {
+            pending.push(PendingStyle::Unchecked {
+                name: name.to_string(),
+                value: value.to_string(),
+            });
+
+        } else {
+            bindings::set_style(self.element(), intern(name), value, false);
+
+            if let Some(extracted) = &mut self.extracted {
+                push_declaration(extracted, name, value, false);
+            }
+        }
+    }
+
+    #[track_caller]
+    fn set_style<B, C>(&mut self, name: &B, value: C, important: bool)
+        where B: MultiStr,
+              C: MultiStr {
+        if let Some(pending) = &mut self.pending {
+            pending.push(PendingStyle::Style {
+                names: PendingStyle::strings(name),
+                values: PendingStyle::strings(&value),
+                important,
+            });
+
+        } else {
+            let element = self.element().clone();
+            set_style(&element, name, value, important, self.extracted.as_mut());
         }
     }
 
+    #[doc(hidden)]
+    #[inline]
+    pub fn __internal_stylesheet<A>(rules: A) -> Self where A: MultiStr {
+        Self::new_rule(rules, true)
+    }
+
     #[doc(hidden)]
     #[inline]
     pub fn __internal_new<A>(rules: A) -> Self where A: MultiStr {
@@ -1382,32 +2217,38 @@ impl StylesheetBuilder {
 
     #[inline]
     #[track_caller]
-    pub fn style<B, C>(self, name: B, value: C) -> Self
+    pub fn style<B, C>(mut self, name: B, value: C) -> Self
         where B: MultiStr,
               C: MultiStr {
-        set_style(&self.element, &name, value, false);
+        if !self.is_precomputed {
+            self.set_style(&name, value, false);
+        }
         self
     }
 
     #[inline]
     #[track_caller]
-    pub fn style_important<B, C>(self, name: B, value: C) -> Self
+    pub fn style_important<B, C>(mut self, name: B, value: C) -> Self
         where B: MultiStr,
               C: MultiStr {
-        set_style(&self.element, &name, value, true);
+        if !self.is_precomputed {
+            self.set_style(&name, value, true);
+        }
         self
     }
 
     #[inline]
     #[track_caller]
-    pub fn style_unchecked<B, C>(self, name: B, value: C) -> Self
+    pub fn style_unchecked<B, C>(mut self, name: B, value: C) -> Self
         where B: AsStr,
               C: AsStr {
-        name.with_str(|name| {
-            value.with_str(|value| {
-                bindings::set_style(&self.element, intern(name), value, false);
+        if !self.is_precomputed {
+            name.with_str(|name| {
+                value.with_str(|value| {
+                    self.set_style_unchecked(name, value);
+                });
             });
-        });
+        }
         self
     }
 
@@ -1419,7 +2260,7 @@ impl StylesheetBuilder {
               D: OptionStr<Output = C>,
               E: Signal<Item = D> + 'static {
 
-        set_style_signal(self.element.clone(), &mut self.callbacks, name, value, false);
+        set_style_signal(self.element().clone(), &mut self.callbacks, name, value, false);
         self
     }
 
@@ -1431,7 +2272,7 @@ impl StylesheetBuilder {
               D: OptionStr<Output = C>,
               E: Signal<Item = D> + 'static {
 
-        set_style_signal(self.element.clone(), &mut self.callbacks, name, value, true);
+        set_style_signal(self.element().clone(), &mut self.callbacks, name, value, true);
         self
     }
 
@@ -1443,7 +2284,52 @@ impl StylesheetBuilder {
               D: OptionStr<Output = C>,
               E: Signal<Item = D> + 'static {
 
-        set_style_unchecked_signal(self.element.clone(), &mut self.callbacks, name, value, false);
+        set_style_unchecked_signal(self.element().clone(), &mut self.callbacks, name, value, false);
+        self
+    }
+
+    /// Sets a CSS custom property (variable). The name must start with `--`.
+    #[inline]
+    #[track_caller]
+    pub fn css_var<B, C>(self, name: B, value: C) -> Self
+        where B: AsStr,
+              C: AsStr {
+        assert_css_var(&name);
+        self.style_unchecked(name, value)
+    }
+
+    /// Sets a CSS custom property (variable), and updates it whenever the signal changes.
+    #[inline]
+    #[track_caller]
+    pub fn css_var_signal<B, C, D, E>(self, name: B, value: E) -> Self
+        where B: AsStr + 'static,
+              C: AsStr,
+              D: OptionStr<Output = C>,
+              E: Signal<Item = D> + 'static {
+        assert_css_var(&name);
+        self.style_unchecked_signal(name, value)
+    }
+
+    /// Sets all of the [`Theme`]'s custom properties.
+    #[inline]
+    #[track_caller]
+    pub fn theme<B>(mut self, theme: B) -> Self where B: Theme {
+        if !self.is_precomputed {
+            theme.each_var(|name, value| {
+                assert_css_var(&name);
+                self.set_style_unchecked(name, value);
+            });
+        }
+        self
+    }
+
+    /// Sets all of the [`Theme`]'s custom properties, and switches the theme whenever the signal changes.
+    #[inline]
+    #[track_caller]
+    pub fn theme_signal<B, C>(mut self, theme: C) -> Self
+        where B: Theme,
+              C: Signal<Item = B> + 'static {
+        set_theme_signal(self.element().clone(), &mut self.callbacks, theme);
         self
     }
 
@@ -1467,10 +2353,22 @@ impl StylesheetBuilder {
     /// ```
     #[inline]
     #[track_caller]
-    pub fn raw<B>(self, css: B) -> Self where B: AsStr {
-        css.with_str(|css| {
-            bindings::append_raw(&self.element, css);
-        });
+    pub fn raw<B>(mut self, css: B) -> Self where B: AsStr {
+        if let Some(pending) = &mut self.pending {
+            css.with_str(|css| {
+                pending.push(PendingStyle::Raw(css.to_string()));
+            });
+
+        } else if !self.is_precomputed {
+            css.with_str(|css| {
+                bindings::append_raw(self.element(), css);
+
+                if let Some(extracted) = &mut self.extracted {
+                    extracted.push_str(css.trim());
+                    extracted.push(' ');
+                }
+            });
+        }
 
         self
     }
@@ -1480,11 +2378,32 @@ impl StylesheetBuilder {
     #[track_caller]
     #[doc(hidden)]
     pub fn __internal_done(mut self) {
+        if let (Some(rule), Some(extracted)) = (&self.rule, &self.extracted) {
+            if !extracted.is_empty() {
+                let selector = rule.rule.unchecked_ref::<CssStyleRule>().selector_text();
+
+                EXTRACTED_CSS.with(|css| {
+                    let mut css = css.borrow_mut();
+                    css.push_str(&selector);
+                    css.push_str(" { ");
+                    css.push_str(extracted);
+                    css.push_str("}\n");
+                });
+            }
+        }
+
         self.callbacks.trigger_after_insert();
 
         // This prevents it from triggering after_remove
         self.callbacks.leak();
     }
+
+    /// Unlike `__internal_done` this keeps ownership of the callbacks, so the rule can be removed later.
+    fn into_removable(mut self) -> (CssRule, Callbacks) {
+        self.element();
+        self.callbacks.trigger_after_insert();
+        (self.rule.unwrap_throw().rule, self.callbacks)
+    }
 }
 
 
@@ -1492,20 +2411,45 @@ impl StylesheetBuilder {
 #[must_use]
 pub struct ClassBuilder {
     stylesheet: StylesheetBuilder,
+    pseudos: Vec<StylesheetBuilder>,
     class_name: String,
+    is_static: bool,
 }
 
 impl ClassBuilder {
     #[doc(hidden)]
     #[inline]
     #[track_caller]
-    pub fn __internal_new(name: Option<&str>) -> Self {
-        let class_name = __internal::make_class_id(name);
+    pub fn __internal_new(name: Option<&str>, module: &'static str) -> Self {
+        let class_name = if let StaticCss::Runtime = STATIC_CSS.with(|mode| mode.get()) {
+            __internal::make_class_id(name)
+
+        } else {
+            // The class name must be the same in every build, so it cannot use a counter
+            __internal::make_static_class_id(name, module, std::panic::Location::caller())
+        };
 
         Self {
             // TODO make this more efficient ?
             stylesheet: StylesheetBuilder::__internal_stylesheet(&format!(".{} {{}}", class_name)),
+            pseudos: vec![],
+            class_name,
+            is_static: true,
+        }
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    #[track_caller]
+    pub fn __internal_new_dynamic(name: Option<&str>) -> Self {
+        let class_name = __internal::make_class_id(name);
+
+        Self {
+            // Dynamic classes are removed later, so they are never precomputed
+            stylesheet: StylesheetBuilder::new_dynamic(format!(".{}", class_name)),
+            pseudos: vec![],
             class_name,
+            is_static: false,
         }
     }
 
@@ -1516,9 +2460,30 @@ impl ClassBuilder {
         &self.class_name
     }
 
+    #[doc(hidden)]
     #[inline]
     #[track_caller]
-    pub fn style<B, C>(mut self, name: B, value: C) -> Self
+    pub fn __internal_pseudo<A>(&self, pseudos: A) -> StylesheetBuilder where A: MultiStr {
+        let pseudos = __internal::Pseudo::new(&self.class_name, pseudos);
+
+        if self.is_static {
+            StylesheetBuilder::__internal_new(pseudos)
+
+        } else {
+            StylesheetBuilder::new_dynamic(pseudos)
+        }
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn __internal_pseudo_done(mut self, pseudo: StylesheetBuilder) -> Self {
+        self.pseudos.push(pseudo);
+        self
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn style<B, C>(mut self, name: B, value: C) -> Self
         where B: MultiStr,
               C: MultiStr {
         self.stylesheet = self.stylesheet.style(name, value);
@@ -1579,6 +2544,46 @@ impl ClassBuilder {
         self
     }
 
+    /// Sets a CSS custom property (variable). The name must start with `--`.
+    #[inline]
+    #[track_caller]
+    pub fn css_var<B, C>(mut self, name: B, value: C) -> Self
+        where B: AsStr,
+              C: AsStr {
+        self.stylesheet = self.stylesheet.css_var(name, value);
+        self
+    }
+
+    /// Sets a CSS custom property (variable), and updates it whenever the signal changes.
+    #[inline]
+    #[track_caller]
+    pub fn css_var_signal<B, C, D, E>(mut self, name: B, value: E) -> Self
+        where B: AsStr + 'static,
+              C: AsStr,
+              D: OptionStr<Output = C>,
+              E: Signal<Item = D> + 'static {
+        self.stylesheet = self.stylesheet.css_var_signal(name, value);
+        self
+    }
+
+    /// Sets all of the [`Theme`]'s custom properties.
+    #[inline]
+    #[track_caller]
+    pub fn theme<B>(mut self, theme: B) -> Self where B: Theme {
+        self.stylesheet = self.stylesheet.theme(theme);
+        self
+    }
+
+    /// Sets all of the [`Theme`]'s custom properties, and switches the theme whenever the signal changes.
+    #[inline]
+    #[track_caller]
+    pub fn theme_signal<B, C>(mut self, theme: C) -> Self
+        where B: Theme,
+              C: Signal<Item = B> + 'static {
+        self.stylesheet = self.stylesheet.theme_signal(theme);
+        self
+    }
+
     /// Appends raw CSS code into the class.
     ///
     /// It is recommended to use the various `.style` methods instead.
@@ -1610,229 +2615,1712 @@ impl ClassBuilder {
     #[track_caller]
     pub fn __internal_done(self) -> String {
         self.stylesheet.__internal_done();
+
+        for pseudo in self.pseudos {
+            pseudo.__internal_done();
+        }
+
         self.class_name
     }
-}
 
+    // Signals can change the styles later, so only static classes are deduplicated
+    // TODO deduplicate classes inside of adopted stylesheets
+    fn dynamic_key(&self) -> Option<String> {
+        let mut keys = vec![self.stylesheet.pending_key(&self.class_name)?];
 
-#[doc(hidden)]
-pub mod __internal {
-    use std::sync::atomic::{AtomicU32, Ordering};
-    use crate::fragment::{Fragment, FragmentBuilder, BoxFragment};
-    use crate::traits::MultiStr;
+        for pseudo in self.pseudos.iter() {
+            keys.push(pseudo.pending_key(&self.class_name)?);
+        }
 
+        Some(keys.join("\n"))
+    }
 
-    pub use web_sys::HtmlElement;
-    pub use web_sys::SvgElement;
+    #[doc(hidden)]
+    #[inline]
+    #[track_caller]
+    pub fn __internal_done_dynamic(self) -> DynamicClass {
+        let key = self.dynamic_key();
 
+        if let Some(key) = &key {
+            let existing = DYNAMIC_CLASSES.with(|classes| {
+                classes.borrow().get(key).and_then(|class| class.upgrade())
+            });
 
-    pub fn make_class_id(name: Option<&str>) -> String {
-        // TODO replace this with a global counter in JavaScript ?
-        // TODO can this be made more efficient ?
-        static CLASS_ID: AtomicU32 = AtomicU32::new(0);
+            // The rules haven't been created yet, so nothing needs to be removed
+            if let Some(existing) = existing {
+                return DynamicClass { state: existing };
+            }
+        }
 
-        // TODO check for overflow ?
-        // TODO should this be SeqCst ?
-        let id = CLASS_ID.fetch_add(1, Ordering::Relaxed);
+        let class_name = self.class_name;
 
-        let name = name.unwrap_or("__class_");
-        // TODO make this more efficient ?
-        format!("{}_{}", name, id)
-    }
+        let mut rules = Vec::with_capacity(self.pseudos.len() + 1);
+        let mut callbacks = Callbacks::new();
 
+        for stylesheet in std::iter::once(self.stylesheet).chain(self.pseudos) {
+            let (rule, mut stylesheet_callbacks) = stylesheet.into_removable();
+            callbacks.after_remove.append(&mut stylesheet_callbacks.after_remove);
+            rules.push(rule);
+        }
 
-    pub struct Pseudo<'a, A> {
-        class_name: &'a str,
-        pseudos: A,
-    }
+        let state = Rc::new(DynamicClassState {
+            class_name,
+            key,
+            rules,
+            _callbacks: callbacks,
+        });
 
-    impl<'a, A> Pseudo<'a, A> where A: MultiStr {
-        #[inline]
-        pub fn new(class_name: &'a str, pseudos: A) -> Self {
-            Self { class_name, pseudos }
+        if let Some(key) = &state.key {
+            DYNAMIC_CLASSES.with(|classes| {
+                classes.borrow_mut().insert(key.clone(), Rc::downgrade(&state));
+            });
         }
-    }
 
-    impl<'a, A> MultiStr for Pseudo<'a, A> where A: MultiStr {
-        #[inline]
-        fn find_map<B, F>(&self, mut f: F) -> Option<B> where F: FnMut(&str) -> Option<B> {
-            self.pseudos.find_map(|x| {
-                f(&format!(".{}{}", self.class_name, x))
-            })
-        }
+        DynamicClass { state }
     }
+}
 
 
-    #[derive(Debug)]
-    struct FnFragment<F>(F);
+thread_local! {
+    static DYNAMIC_CLASSES: RefCell<HashMap<String, Weak<DynamicClassState>>> = RefCell::new(HashMap::new());
+}
 
-    impl<F> Fragment for FnFragment<F> where F: Fn(FragmentBuilder<'_>) -> FragmentBuilder<'_> {
-        #[inline]
-        fn apply<'a>(&self, dom: FragmentBuilder<'a>) -> FragmentBuilder<'a> {
-            (self.0)(dom)
+struct DynamicClassState {
+    class_name: String,
+    key: Option<String>,
+    rules: Vec<CssRule>,
+    // This stops the signals when the class is dropped
+    _callbacks: Callbacks,
+}
+
+impl Drop for DynamicClassState {
+    fn drop(&mut self) {
+        if let Some(key) = &self.key {
+            DYNAMIC_CLASSES.with(|classes| {
+                let mut classes = classes.borrow_mut();
+
+                if classes.get(key).map(|class| class.strong_count() == 0).unwrap_or(false) {
+                    classes.remove(key);
+                }
+            });
+        }
+
+        for rule in self.rules.iter() {
+            bindings::remove_rule(rule);
         }
     }
+}
+
+/// A reference-counted class which is created with the [`dynamic_class!`] macro.
+///
+/// When the last clone of the `DynamicClass` is dropped, its CSS rules are removed
+/// from the stylesheet, so it is safe to create classes at runtime.
+///
+/// A `DynamicClass` can be assigned to a [`DomBuilder`] by using
+/// [`DomBuilder::dynamic_class`] or [`DomBuilder::dynamic_class_signal`], which
+/// will keep the class alive for as long as the element exists.
+#[derive(Clone)]
+pub struct DynamicClass {
+    state: Rc<DynamicClassState>,
+}
 
+impl DynamicClass {
     #[inline]
-    pub fn fragment<F>(f: F) -> impl Fragment where F: Fn(FragmentBuilder<'_>) -> FragmentBuilder<'_> {
-        FnFragment(f)
+    pub fn class_name(&self) -> &str {
+        &self.state.class_name
     }
+}
 
+impl PartialEq for DynamicClass {
     #[inline]
-    pub fn box_fragment<F>(f: F) -> BoxFragment where F: Fn(FragmentBuilder<'_>) -> FragmentBuilder<'_> + Send + Sync + 'static {
-        Box::new(FnFragment(f))
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.state, &other.state)
     }
 }
 
+impl Eq for DynamicClass {}
 
-#[cfg(test)]
-mod tests {
-    use super::{DomBuilder, text_signal, RefFn};
-    use crate::{html, shadow_root, ShadowRootMode, with_cfg};
-    use futures_signals::signal::{always, SignalExt};
-    use once_cell::sync::Lazy;
-    use web_sys::HtmlElement;
-
-    #[test]
-    fn apply() {
-        let a: DomBuilder<HtmlElement> = DomBuilder::new_html("div");
-
-        fn my_mixin<A: AsRef<HtmlElement>>(builder: DomBuilder<A>) -> DomBuilder<A> {
-            builder.style("foo", "bar")
-        }
-
-        let _ = a.apply(my_mixin);
+impl std::fmt::Debug for DynamicClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("DynamicClass")
+            .field(&self.state.class_name)
+            .finish()
     }
+}
 
-    #[test]
-    fn children_mut() {
-        let _a: DomBuilder<HtmlElement> = DomBuilder::new_html("div")
-            .children(&mut [
-                DomBuilder::<HtmlElement>::new_html("div").into_dom(),
-                DomBuilder::<HtmlElement>::new_html("div").into_dom(),
-                DomBuilder::<HtmlElement>::new_html("div").into_dom(),
-            ]);
+impl AsStr for DynamicClass {
+    #[inline]
+    fn as_str(&self) -> &str {
+        self.class_name()
     }
 
-    #[test]
-    fn children_value() {
-        let v: Vec<u32> = vec![];
-
-        let _a: DomBuilder<HtmlElement> = DomBuilder::new_html("div")
-            .children(v.iter().map(|_| {
-                DomBuilder::<HtmlElement>::new_html("div").into_dom()
-            }));
+    #[inline]
+    fn with_str<A, F>(&self, f: F) -> A where F: FnOnce(&str) -> A {
+        f(self.class_name())
     }
+}
 
-    #[test]
-    fn text_signal_types() {
-        let _ = text_signal(always("foo"));
-        let _ = text_signal(always("foo".to_owned()));
-        let _ = text_signal(always("foo".to_owned()).map(|x| RefFn::new(x, |x| x.as_str())));
-        //text_signal(always(Arc::new("foo")));
-        //text_signal(always(Arc::new("foo".to_owned())));
-        //text_signal(always(Rc::new("foo")));
-        //text_signal(always(Rc::new("foo".to_owned())));
-        //text_signal(always(Box::new("foo")));
-        //text_signal(always(Box::new("foo".to_owned())));
-        //text_signal(always(Cow::Borrowed(&"foo")));
-        //text_signal(always(Cow::Owned::<String>("foo".to_owned())));
-    }
 
-    #[test]
-    fn property_signal_types() {
-        let _a: DomBuilder<HtmlElement> = DomBuilder::new_html("div")
-            .prop("foo", "hi")
-            .prop("foo", 5)
-            .prop(["foo", "-webkit-foo", "-ms-foo"], "hi")
+#[inline]
+fn keyframes_css(name: &str) -> String {
+    format!("@keyframes {} {{}}", name)
+}
 
-            .prop_signal("foo", always("hi"))
-            .prop_signal("foo", always(5))
-            .prop_signal("foo", always(Some("hi")))
+#[inline]
+fn keyframe_css(percentage: Percentage) -> String {
+    format!("{}% {{}}", percentage.into_f64() * 100.0)
+}
 
-            .prop_signal(["foo", "-webkit-foo", "-ms-foo"], always("hi"))
-            .prop_signal(["foo", "-webkit-foo", "-ms-foo"], always(5))
-            .prop_signal(["foo", "-webkit-foo", "-ms-foo"], always(Some("hi")))
-            ;
-    }
 
-    #[test]
-    fn attribute_signal_types() {
-        let _a: DomBuilder<HtmlElement> = DomBuilder::new_html("div")
-            .attr("foo", "hi")
-            .attr(["foo", "-webkit-foo", "-ms-foo"], "hi")
+// TODO better warning message for must_use
+#[must_use]
+pub struct KeyframesBuilder {
+    rule: CssKeyframesRule,
+    name: String,
+}
 
-            .attr_signal("foo", always("hi"))
-            .attr_signal("foo", always(Some("hi")))
+// The CssKeyframesRule is never removed, just like `class!`, see the docs for `keyframes!`
+impl KeyframesBuilder {
+    #[doc(hidden)]
+    #[inline]
+    #[track_caller]
+    pub fn __internal_new(name: Option<&str>) -> Self {
+        let name = __internal::make_keyframes_id(name);
 
-            .attr_signal(["foo", "-webkit-foo", "-ms-foo"], always("hi"))
-            .attr_signal(["foo", "-webkit-foo", "-ms-foo"], always(Some("hi")))
-            ;
+        Self {
+            // TODO make this more efficient ?
+            rule: StylesheetBuilder::__internal_rules(&keyframes_css(&name)).unchecked_into(),
+            name,
+        }
     }
 
-    #[test]
-    fn class_signal_types() {
-        let _a: DomBuilder<HtmlElement> = DomBuilder::new_html("div")
-            .class("foo")
-            .class(["foo", "-webkit-foo", "-ms-foo"])
-
-            .class_signal("foo", always(true))
-            .class_signal(["foo", "-webkit-foo", "-ms-foo"], always(true))
-            ;
+    #[doc(hidden)]
+    #[inline]
+    #[track_caller]
+    pub fn __internal_frame(&self, percentage: Percentage) -> StylesheetBuilder {
+        // TODO make this more efficient ?
+        self.rule.append_rule(&keyframe_css(percentage));
+
+        let rules = self.rule.css_rules();
+
+        // TODO use dyn_into ?
+        let frame: CssKeyframeRule = rules.get(rules.length() - 1).unwrap_throw().unchecked_into();
+
+        StylesheetBuilder {
+            rule: Some(StylesheetRule {
+                element: frame.style(),
+                rule: frame.into(),
+            }),
+            selectors: vec![],
+            is_precomputed: false,
+            extracted: None,
+            pending: None,
+            callbacks: Callbacks::new(),
+        }
     }
 
-    #[test]
-    fn style_signal_types() {
-        static FOO: Lazy<String> = Lazy::new(|| "foo".to_owned());
+    // TODO return a Handle ?
+    #[doc(hidden)]
+    #[inline]
+    #[track_caller]
+    pub fn __internal_done(self) -> String {
+        self.name
+    }
+}
 
-        let _a: DomBuilder<HtmlElement> = DomBuilder::new_html("div")
-            .style_signal("foo", always("bar"))
-            .style_signal("foo", always("bar".to_owned()))
-            .style_signal("foo", always("bar".to_owned()).map(|x| RefFn::new(x, |x| x.as_str())))
 
-            .style("foo".to_owned(), "bar".to_owned())
-            .style_signal("foo".to_owned(), always("bar".to_owned()))
+#[inline]
+fn push_descriptor(descriptors: &mut String, name: &str, value: &str) {
+    descriptors.push_str(name);
+    descriptors.push_str(": ");
+    descriptors.push_str(value);
+    descriptors.push_str("; ");
+}
 
-            .style(&"foo".to_owned(), &"bar".to_owned())
-            //.style(Box::new("foo".to_owned()), Box::new("bar".to_owned()))
-            //.style_signal(Box::new("foo".to_owned()), always(Box::new("bar".to_owned())))
+#[inline]
+fn at_rule_css(kind: &str, prelude: &str, descriptors: &str) -> String {
+    format!("@{} {} {{ {} }}", kind, prelude, descriptors)
+}
 
-            .style_signal(&*FOO, always(&*FOO))
+#[track_caller]
+fn make_at_rule(rule: &str) {
+    // Unknown descriptors are silently dropped by the browser, so this only checks the syntax
+    // These at-rules are ignored inside of shadow roots, so they always go into the document
+    StylesheetBuilder::document_rules(&rule);
+}
 
-            //.style(vec!["-moz-foo", "-webkit-foo", "foo"].as_slice(), vec!["bar"].as_slice())
-            .style_signal(RefFn::new(vec!["-moz-foo", "-webkit-foo", "foo"], |x| x.as_slice()), always(RefFn::new(vec!["bar"], |x| x.as_slice())))
 
-            .style_signal(["-moz-foo", "-webkit-foo", "foo"], always("bar"))
-            .style_signal(["-moz-foo", "-webkit-foo", "foo"], always("bar".to_owned()))
-            .style_signal(["-moz-foo", "-webkit-foo", "foo"], always("bar".to_owned()).map(|x| RefFn::new(x, |x| x.as_str())))
+// TODO better warning message for must_use
+#[must_use]
+pub struct FontFaceBuilder {
+    name: String,
+    descriptors: String,
+}
+
+impl FontFaceBuilder {
+    #[doc(hidden)]
+    #[inline]
+    pub fn __internal_new(name: Option<&str>) -> Self {
+        let name = __internal::make_font_face_id(name);
+
+        let mut descriptors = String::new();
+        push_descriptor(&mut descriptors, "font-family", &format!("\"{}\"", name));
+
+        Self { name, descriptors }
+    }
+
+    /// Sets a [descriptor](https://developer.mozilla.org/en-US/docs/Web/CSS/@font-face#descriptors) for the `@font-face` rule.
+    ///
+    /// The `font-family` descriptor is automatically generated, so you should not set it.
+    #[inline]
+    pub fn descriptor<B, C>(mut self, name: B, value: C) -> Self
+        where B: AsStr,
+              C: AsStr {
+        name.with_str(|name| {
+            value.with_str(|value| {
+                push_descriptor(&mut self.descriptors, name, value);
+            });
+        });
+        self
+    }
+
+    #[inline]
+    pub fn src<B>(self, value: B) -> Self where B: AsStr {
+        self.descriptor("src", value)
+    }
+
+    #[inline]
+    pub fn font_weight<B>(self, value: B) -> Self where B: AsStr {
+        self.descriptor("font-weight", value)
+    }
+
+    #[inline]
+    pub fn font_style<B>(self, value: B) -> Self where B: AsStr {
+        self.descriptor("font-style", value)
+    }
+
+    #[inline]
+    pub fn font_display<B>(self, value: B) -> Self where B: AsStr {
+        self.descriptor("font-display", value)
+    }
+
+    #[inline]
+    pub fn unicode_range<B>(self, value: B) -> Self where B: AsStr {
+        self.descriptor("unicode-range", value)
+    }
+
+    fn to_css(&self) -> String {
+        at_rule_css("font-face", "", &self.descriptors)
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    #[track_caller]
+    pub fn __internal_done(self) -> String {
+        make_at_rule(&self.to_css());
+        self.name
+    }
+}
+
+
+// TODO better warning message for must_use
+#[must_use]
+pub struct CustomPropertyBuilder {
+    name: String,
+    syntax: Option<String>,
+    inherits: bool,
+    initial_value: Option<String>,
+}
+
+impl CustomPropertyBuilder {
+    #[doc(hidden)]
+    #[inline]
+    pub fn __internal_new(name: Option<&str>) -> Self {
+        Self {
+            name: __internal::make_property_id(name),
+            syntax: None,
+            inherits: false,
+            initial_value: None,
+        }
+    }
+
+    /// Sets the [`syntax`](https://developer.mozilla.org/en-US/docs/Web/CSS/@property/syntax) descriptor.
+    ///
+    /// If this isn't called, it defaults to `"*"`.
+    #[inline]
+    pub fn syntax<B>(mut self, value: B) -> Self where B: AsStr {
+        self.syntax = Some(value.with_str(|value| String::from(value)));
+        self
+    }
+
+    /// Sets the [`inherits`](https://developer.mozilla.org/en-US/docs/Web/CSS/@property/inherits) descriptor.
+    ///
+    /// If this isn't called, it defaults to `false`.
+    #[inline]
+    pub fn inherits(mut self, value: bool) -> Self {
+        self.inherits = value;
+        self
+    }
+
+    /// Sets the [`initial-value`](https://developer.mozilla.org/en-US/docs/Web/CSS/@property/initial-value) descriptor.
+    ///
+    /// This is required if the syntax is not `"*"`.
+    #[inline]
+    pub fn initial_value<B>(mut self, value: B) -> Self where B: AsStr {
+        self.initial_value = Some(value.with_str(|value| String::from(value)));
+        self
+    }
+
+    #[track_caller]
+    fn to_css(&self) -> String {
+        let mut descriptors = String::new();
+
+        let syntax = self.syntax.as_deref().unwrap_or("*");
+
+        push_descriptor(&mut descriptors, "syntax", &format!("\"{}\"", syntax));
+        push_descriptor(&mut descriptors, "inherits", if self.inherits { "true" } else { "false" });
+
+        if let Some(initial_value) = &self.initial_value {
+            push_descriptor(&mut descriptors, "initial-value", initial_value);
+
+        } else if syntax != "*" {
+            panic!("@property {} must have an initial-value because its syntax is {}", self.name, syntax);
+        }
+
+        at_rule_css("property", &self.name, &descriptors)
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    #[track_caller]
+    pub fn __internal_done(self) -> String {
+        make_at_rule(&self.to_css());
+        self.name
+    }
+}
+
+
+#[doc(hidden)]
+pub mod __internal {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use crate::fragment::{Fragment, FragmentBuilder, BoxFragment};
+    use crate::traits::MultiStr;
+
+
+    pub use web_sys::HtmlElement;
+    pub use web_sys::SvgElement;
+
+
+    fn make_id(name: Option<&str>, default: &str) -> String {
+        // TODO replace this with a global counter in JavaScript ?
+        // TODO can this be made more efficient ?
+        static CLASS_ID: AtomicU32 = AtomicU32::new(0);
+
+        // TODO check for overflow ?
+        // TODO should this be SeqCst ?
+        let id = CLASS_ID.fetch_add(1, Ordering::Relaxed);
+
+        let name = name.unwrap_or(default);
+        // TODO make this more efficient ?
+        format!("{}_{}", name, id)
+    }
+
+    pub fn make_class_id(name: Option<&str>) -> String {
+        make_id(name, "__class_")
+    }
+
+    // FNV-1a, because it needs to be stable between builds and between machines
+    pub(crate) fn static_class_hash(module: &str, name: &str, line: u32, column: u32, occurrence: u32) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+
+        let mut write = |bytes: &[u8]| {
+            for byte in bytes {
+                hash ^= *byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        };
+
+        // The lengths are included so that different module and name pairs can't produce the same bytes
+        write(&(module.len() as u32).to_le_bytes());
+        write(module.as_bytes());
+        write(&(name.len() as u32).to_le_bytes());
+        write(name.as_bytes());
+        write(&line.to_le_bytes());
+        write(&column.to_le_bytes());
+        write(&occurrence.to_le_bytes());
+
+        hash
+    }
+
+    /// Generates a class name which only depends on the module, the name, and the line and column of the `class!` macro.
+    ///
+    /// It doesn't use the file path, because that is different on every machine.
+    pub fn make_static_class_id(name: Option<&str>, module: &'static str, location: &'static std::panic::Location<'static>) -> String {
+        thread_local! {
+            static OCCURRENCES: RefCell<HashMap<(&'static str, u32, u32), u32>> = RefCell::new(HashMap::new());
+            // Used to detect hash collisions when extracting the CSS
+            static CLASS_NAMES: RefCell<HashMap<String, (&'static str, u32, u32)>> = RefCell::new(HashMap::new());
+        }
+
+        let key = (module, location.line(), location.column());
+
+        // The same location can be called multiple times (e.g. in a loop)
+        let occurrence = OCCURRENCES.with(|occurrences| {
+            let mut occurrences = occurrences.borrow_mut();
+            let occurrence = occurrences.entry(key).or_insert(0);
+            let old = *occurrence;
+            *occurrence += 1;
+            old
+        });
+
+        let name = name.unwrap_or("__class_");
+        let hash = static_class_hash(module, name, location.line(), location.column(), occurrence);
+        let class_name = format!("{}_{:016x}", name, hash);
+
+        if let super::StaticCss::Extract = super::STATIC_CSS.with(|mode| mode.get()) {
+            CLASS_NAMES.with(|class_names| {
+                let mut class_names = class_names.borrow_mut();
+
+                // Every call has a different occurrence, so the same class name means that the hash collided
+                if let Some((old_module, old_line, old_column)) = class_names.get(&class_name) {
+                    if *old_module != key.0 || *old_line != key.1 || *old_column != key.2 {
+                        panic!(
+                            "class! in {} at line {} column {} has the same class name {} as the class! in {} at line {} column {}, use a different #![prefix]",
+                            key.0, key.1, key.2, class_name, old_module, old_line, old_column,
+                        );
+                    }
+                }
+
+                class_names.insert(class_name.clone(), key);
+            });
+        }
+
+        class_name
+    }
+
+    pub fn make_keyframes_id(name: Option<&str>) -> String {
+        make_id(name, "__keyframes_")
+    }
+
+    pub fn make_font_face_id(name: Option<&str>) -> String {
+        make_id(name, "__font_")
+    }
+
+    pub fn make_property_id(name: Option<&str>) -> String {
+        // Custom properties must always start with --
+        format!("--{}", make_id(name, "__property_"))
+    }
+
+
+    pub struct Pseudo<'a, A> {
+        class_name: &'a str,
+        pseudos: A,
+    }
+
+    impl<'a, A> Pseudo<'a, A> where A: MultiStr {
+        #[inline]
+        pub fn new(class_name: &'a str, pseudos: A) -> Self {
+            Self { class_name, pseudos }
+        }
+    }
+
+    impl<'a, A> MultiStr for Pseudo<'a, A> where A: MultiStr {
+        #[inline]
+        fn find_map<B, F>(&self, mut f: F) -> Option<B> where F: FnMut(&str) -> Option<B> {
+            self.pseudos.find_map(|x| {
+                f(&format!(".{}{}", self.class_name, x))
+            })
+        }
+    }
+
+
+    #[derive(Debug)]
+    struct FnFragment<F>(F);
+
+    impl<F> Fragment for FnFragment<F> where F: Fn(FragmentBuilder<'_>) -> FragmentBuilder<'_> {
+        #[inline]
+        fn apply<'a>(&self, dom: FragmentBuilder<'a>) -> FragmentBuilder<'a> {
+            (self.0)(dom)
+        }
+    }
+
+    #[inline]
+    pub fn fragment<F>(f: F) -> impl Fragment where F: Fn(FragmentBuilder<'_>) -> FragmentBuilder<'_> {
+        FnFragment(f)
+    }
+
+    #[inline]
+    pub fn box_fragment<F>(f: F) -> BoxFragment where F: Fn(FragmentBuilder<'_>) -> FragmentBuilder<'_> + Send + Sync + 'static {
+        Box::new(FnFragment(f))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{DomBuilder, DynamicClass, AdoptedStylesheet, StaticCss, FontFaceBuilder, CustomPropertyBuilder, set_static_css, extracted_css, set_style_nonce, text_signal, keyframes_css, keyframe_css, RefFn};
+    use crate::{html, shadow_root, ShadowRootMode, with_cfg};
+    use crate::animation::Percentage;
+    use crate::theme::{Theme, var, var_or};
+    use futures_signals::signal::{always, SignalExt};
+    use once_cell::sync::Lazy;
+    use web_sys::HtmlElement;
+
+    #[test]
+    fn apply() {
+        let a: DomBuilder<HtmlElement> = DomBuilder::new_html("div");
+
+        fn my_mixin<A: AsRef<HtmlElement>>(builder: DomBuilder<A>) -> DomBuilder<A> {
+            builder.style("foo", "bar")
+        }
+
+        let _ = a.apply(my_mixin);
+    }
+
+    #[test]
+    fn children_mut() {
+        let _a: DomBuilder<HtmlElement> = DomBuilder::new_html("div")
+            .children(&mut [
+                DomBuilder::<HtmlElement>::new_html("div").into_dom(),
+                DomBuilder::<HtmlElement>::new_html("div").into_dom(),
+                DomBuilder::<HtmlElement>::new_html("div").into_dom(),
+            ]);
+    }
+
+    #[test]
+    fn children_value() {
+        let v: Vec<u32> = vec![];
+
+        let _a: DomBuilder<HtmlElement> = DomBuilder::new_html("div")
+            .children(v.iter().map(|_| {
+                DomBuilder::<HtmlElement>::new_html("div").into_dom()
+            }));
+    }
+
+    #[test]
+    fn text_signal_types() {
+        let _ = text_signal(always("foo"));
+        let _ = text_signal(always("foo".to_owned()));
+        let _ = text_signal(always("foo".to_owned()).map(|x| RefFn::new(x, |x| x.as_str())));
+        //text_signal(always(Arc::new("foo")));
+        //text_signal(always(Arc::new("foo".to_owned())));
+        //text_signal(always(Rc::new("foo")));
+        //text_signal(always(Rc::new("foo".to_owned())));
+        //text_signal(always(Box::new("foo")));
+        //text_signal(always(Box::new("foo".to_owned())));
+        //text_signal(always(Cow::Borrowed(&"foo")));
+        //text_signal(always(Cow::Owned::<String>("foo".to_owned())));
+    }
+
+    #[test]
+    fn property_signal_types() {
+        let _a: DomBuilder<HtmlElement> = DomBuilder::new_html("div")
+            .prop("foo", "hi")
+            .prop("foo", 5)
+            .prop(["foo", "-webkit-foo", "-ms-foo"], "hi")
+
+            .prop_signal("foo", always("hi"))
+            .prop_signal("foo", always(5))
+            .prop_signal("foo", always(Some("hi")))
+
+            .prop_signal(["foo", "-webkit-foo", "-ms-foo"], always("hi"))
+            .prop_signal(["foo", "-webkit-foo", "-ms-foo"], always(5))
+            .prop_signal(["foo", "-webkit-foo", "-ms-foo"], always(Some("hi")))
+            ;
+    }
+
+    #[test]
+    fn attribute_signal_types() {
+        let _a: DomBuilder<HtmlElement> = DomBuilder::new_html("div")
+            .attr("foo", "hi")
+            .attr(["foo", "-webkit-foo", "-ms-foo"], "hi")
+
+            .attr_signal("foo", always("hi"))
+            .attr_signal("foo", always(Some("hi")))
+
+            .attr_signal(["foo", "-webkit-foo", "-ms-foo"], always("hi"))
+            .attr_signal(["foo", "-webkit-foo", "-ms-foo"], always(Some("hi")))
+            ;
+    }
+
+    #[test]
+    fn class_signal_types() {
+        let _a: DomBuilder<HtmlElement> = DomBuilder::new_html("div")
+            .class("foo")
+            .class(["foo", "-webkit-foo", "-ms-foo"])
+
+            .class_signal("foo", always(true))
+            .class_signal(["foo", "-webkit-foo", "-ms-foo"], always(true))
+            ;
+    }
+
+    #[test]
+    fn style_signal_types() {
+        static FOO: Lazy<String> = Lazy::new(|| "foo".to_owned());
+
+        let _a: DomBuilder<HtmlElement> = DomBuilder::new_html("div")
+            .style_signal("foo", always("bar"))
+            .style_signal("foo", always("bar".to_owned()))
+            .style_signal("foo", always("bar".to_owned()).map(|x| RefFn::new(x, |x| x.as_str())))
+
+            .style("foo".to_owned(), "bar".to_owned())
+            .style_signal("foo".to_owned(), always("bar".to_owned()))
+
+            .style(&"foo".to_owned(), &"bar".to_owned())
+            //.style(Box::new("foo".to_owned()), Box::new("bar".to_owned()))
+            //.style_signal(Box::new("foo".to_owned()), always(Box::new("bar".to_owned())))
+
+            .style_signal(&*FOO, always(&*FOO))
+
+            //.style(vec!["-moz-foo", "-webkit-foo", "foo"].as_slice(), vec!["bar"].as_slice())
+            .style_signal(RefFn::new(vec!["-moz-foo", "-webkit-foo", "foo"], |x| x.as_slice()), always(RefFn::new(vec!["bar"], |x| x.as_slice())))
+
+            .style_signal(["-moz-foo", "-webkit-foo", "foo"], always("bar"))
+            .style_signal(["-moz-foo", "-webkit-foo", "foo"], always("bar".to_owned()))
+            .style_signal(["-moz-foo", "-webkit-foo", "foo"], always("bar".to_owned()).map(|x| RefFn::new(x, |x| x.as_str())))
 
             .style_signal(["-moz-foo", "-webkit-foo", "foo"], always(["bar", "qux"]))
             .style_signal(["-moz-foo", "-webkit-foo", "foo"], always(["bar".to_owned(), "qux".to_owned()]))
 
-            //.style_signal(["-moz-foo", "-webkit-foo", "foo"], always(AsSlice::new(["foo", "bar"])))
-            //.style_signal(["-moz-foo", "-webkit-foo", "foo"], always(("bar".to_owned(), "qux".to_owned())).map(|x| RefFn::new(x, |x| AsSlice::new([x.0.as_str(), x.1.as_str()]))))
+            //.style_signal(["-moz-foo", "-webkit-foo", "foo"], always(AsSlice::new(["foo", "bar"])))
+            //.style_signal(["-moz-foo", "-webkit-foo", "foo"], always(("bar".to_owned(), "qux".to_owned())).map(|x| RefFn::new(x, |x| AsSlice::new([x.0.as_str(), x.1.as_str()]))))
+
+            .style_signal("foo", always(Some("bar")))
+            .style_signal("foo", always(Some("bar".to_owned())))
+            .style_signal("foo", always("bar".to_owned()).map(|x| Some(RefFn::new(x, |x| x.as_str()))))
+
+            .style_signal(["-moz-foo", "-webkit-foo", "foo"], always(Some("bar")))
+            .style_signal(["-moz-foo", "-webkit-foo", "foo"], always(Some("bar".to_owned())))
+            .style_signal(["-moz-foo", "-webkit-foo", "foo"], always("bar".to_owned()).map(|x| Some(RefFn::new(x, |x| x.as_str()))))
+            ;
+    }
+
+    #[test]
+    fn shadow_root() {
+        let _a = html!("div", {
+            .shadow_root!(ShadowRootMode::Closed => {
+                .children(&mut [
+                    html!("span")
+                ])
+            })
+        });
+    }
+
+    #[test]
+    fn at_rules() {
+        let _name: String = keyframes! {
+            #![prefix = "fade"]
+            .keyframe!(Percentage::START, {
+                .style("opacity", "0")
+            })
+            .keyframe!(Percentage::new(0.5))
+            .keyframe!(Percentage::END, {
+                .style_signal("opacity", always("1"))
+            })
+        };
+
+        let _font: String = font_face! {
+            .src("url(foo.woff2)")
+            .descriptor("font-weight", "bold")
+        };
+
+        let _property: String = custom_property! {
+            .syntax("<length>")
+            .inherits(false)
+            .initial_value("0px")
+        };
+    }
+
+    #[test]
+    fn at_rules_css() {
+        assert_eq!(keyframes_css("fade_0"), "@keyframes fade_0 {}");
+        assert_eq!(keyframe_css(Percentage::START), "0% {}");
+        assert_eq!(keyframe_css(Percentage::new(0.5)), "50% {}");
+        assert_eq!(keyframe_css(Percentage::END), "100% {}");
+
+        let font = FontFaceBuilder::__internal_new(Some("foo"))
+            .src("url(foo.woff2)")
+            .font_weight("bold");
+
+        assert_eq!(font.to_css(), format!("@font-face  {{ font-family: \"{}\"; src: url(foo.woff2); font-weight: bold;  }}", font.name));
+
+        let property = CustomPropertyBuilder::__internal_new(Some("size"))
+            .syntax("<length>")
+            .inherits(true)
+            .initial_value("0px");
+
+        assert!(property.name.starts_with("--size_"));
+        assert_eq!(property.to_css(), format!("@property {} {{ syntax: \"<length>\"; inherits: true; initial-value: 0px;  }}", property.name));
+
+        let property = CustomPropertyBuilder::__internal_new(None);
+        assert_eq!(property.to_css(), format!("@property {} {{ syntax: \"*\"; inherits: false;  }}", property.name));
+    }
+
+    #[test]
+    #[should_panic]
+    fn at_rules_css_initial_value() {
+        let _ = CustomPropertyBuilder::__internal_new(None).syntax("<length>").to_css();
+    }
+
+    #[test]
+    fn dynamic_class() {
+        let class: DynamicClass = dynamic_class! {
+            .style("color", "green")
+            .pseudo!(":hover", {
+                .style_signal("color", always("blue"))
+            })
+        };
+
+        let _a: DomBuilder<HtmlElement> = DomBuilder::new_html("div")
+            .dynamic_class(&class)
+            .dynamic_class_signal(always(Some(class.clone())))
+            .class(&class);
+
+        let make_static = || dynamic_class! {
+            .style("color", "green")
+            .pseudo!(":hover", {
+                .style("color", "blue")
+            })
+        };
+
+        let first = make_static();
+        let second = make_static();
+        assert_eq!(first.class_name(), second.class_name());
+        assert_eq!(first, second);
+
+        let make_signal = || dynamic_class! {
+            .style_signal("color", always("green"))
+        };
+
+        assert_ne!(make_signal().class_name(), make_signal().class_name());
+    }
+
+    #[test]
+    fn dynamic_class_key() {
+        use super::{ClassBuilder, replace_class_name};
+
+        assert_eq!(replace_class_name(".foo_1:hover, .foo_1:focus {}", "foo_1"), "&:hover, &:focus {}");
+        assert_eq!(replace_class_name(".foo_1 > .foo_12 {}", "foo_1"), "& > .foo_12 {}");
+
+        let make = |color: &str| apply_methods!(ClassBuilder::__internal_new_dynamic(None), {
+            .style("color", color)
+            .style(["display", "-webkit-display"], "flex")
+            .pseudo!(":hover", {
+                .style_important("color", "blue")
+            })
+        });
+
+        let first = make("green");
+        let second = make("green");
+
+        assert_ne!(first.class_name, second.class_name);
+        assert_eq!(first.dynamic_key(), second.dynamic_key());
+        assert_ne!(first.dynamic_key(), make("red").dynamic_key());
+
+        assert_eq!(
+            first.dynamic_key().unwrap(),
+            "& {} { color: green; display | -webkit-display: flex; }\n&:hover {} { color: blue !important; }",
+        );
+    }
+
+    #[test]
+    fn static_css() {
+        set_static_css(StaticCss::Extract);
+
+        let _class: String = class! {
+            .style("color", "green")
+        };
+
+        let _css: String = extracted_css();
+    }
+
+    #[test]
+    fn static_class_id() {
+        use super::__internal::{make_static_class_id, static_class_hash};
+
+        // The hash must never change, otherwise precomputed CSS won't match
+        assert_eq!(static_class_hash("app::widgets", "button", 10, 5, 0), static_class_hash("app::widgets", "button", 10, 5, 0));
+        assert_ne!(static_class_hash("app::widgets", "button", 10, 5, 0), static_class_hash("app::widgets", "button", 10, 5, 1));
+        assert_ne!(static_class_hash("app::widget", "sbutton", 10, 5, 0), static_class_hash("app::widgets", "button", 10, 5, 0));
+        assert_eq!(static_class_hash("", "", 0, 0, 0), 0xee85fafd354b0935);
+
+        let names: Vec<String> = (0..2).map(|_| make_static_class_id(Some("foo"), module_path!(), std::panic::Location::caller())).collect();
+
+        assert_ne!(names[0], names[1]);
+        assert!(names[0].starts_with("foo_"));
+        assert_eq!(names[0].len(), "foo_".len() + 16);
+    }
+
+    #[test]
+    fn theme() {
+        struct Colors {
+            accent: &'static str,
+        }
+
+        impl Theme for Colors {
+            fn each_var<F>(&self, mut f: F) where F: FnMut(&str, &str) {
+                f("--accent", self.accent);
+            }
+        }
+
+        static LIGHT: Colors = Colors { accent: "blue" };
+
+        let _a: DomBuilder<HtmlElement> = DomBuilder::new_html("div")
+            .css_var("--foo", "green")
+            .css_var_signal("--bar", always(Some("red")))
+            .theme(&LIGHT)
+            .theme_signal(always(&LIGHT))
+            .class(class! {
+                .css_var("--foo", "green")
+                .theme(&LIGHT)
+                .style("color", var("--accent"))
+                .style("background-color", var_or("--background", "white"))
+            });
+    }
+
+    #[test]
+    fn adopted_stylesheet() {
+        let sheet = AdoptedStylesheet::new();
+
+        let class: String = sheet.with(|| class! {
+            .style("color", "green")
+        });
+
+        let _a = html!("div", {
+            .shadow_root!(ShadowRootMode::Open => {
+                .adopt_stylesheet(&sheet)
+                .child(html!("span", {
+                    .class(&class)
+                }))
+            })
+        });
+    }
 
-            .style_signal("foo", always(Some("bar")))
-            .style_signal("foo", always(Some("bar".to_owned())))
-            .style_signal("foo", always("bar".to_owned()).map(|x| Some(RefFn::new(x, |x| x.as_str()))))
+    #[test]
+    fn style_nonce() {
+        set_style_nonce("foo");
+        set_style_nonce(String::from("foo"));
+    }
 
-            .style_signal(["-moz-foo", "-webkit-foo", "foo"], always(Some("bar")))
-            .style_signal(["-moz-foo", "-webkit-foo", "foo"], always(Some("bar".to_owned())))
-            .style_signal(["-moz-foo", "-webkit-foo", "foo"], always("bar".to_owned()).map(|x| Some(RefFn::new(x, |x| x.as_str()))))
-            ;
+    #[test]
+    fn stylesheet_raw_import() {
+        // Without set_stylesheet, raw CSS always gets its own <style>, so @import works
+        assert!(super::custom_stylesheet().is_none());
+
+        let _insert = || super::stylesheet_raw(r#"
+            @import url("foo.css");
+            div { color: green; }
+        "#);
     }
 
     #[test]
-    fn shadow_root() {
+    fn css_values() {
+        use crate::css::{Px, Percent, Deg, Rgb, Rgba, Transform};
+        use crate::traits::MultiStr;
+
+        fn css<A>(value: A) -> String where A: MultiStr {
+            value.find_map(|value| Some(value.to_string())).unwrap()
+        }
+
+        assert_eq!(Px(50.0).to_string(), "50px");
+        assert_eq!(css(Px(50.0)), "50px");
+        assert_eq!(css(Px(-0.5)), "-0.5px");
+        assert_eq!(css(Percent(100.0)), "100%");
+        assert_eq!(css(Rgb(255, 0, 0)), "rgb(255, 0, 0)");
+        assert_eq!(css(Rgba(255, 0, 0, 0.5)), "rgba(255, 0, 0, 0.5)");
+        assert_eq!(css(Transform::new()), "none");
+        assert_eq!(
+            css(Transform::new().translate(Px(10.0), Percent(20.0)).rotate(Deg(45.0)).scale(1.5)),
+            "translate(10px, 20%) rotate(45deg) scale(1.5)",
+        );
+
+        // This is longer than the stack buffer, so it falls back to a String
+        let long = Transform::new()
+            .translate(Px(1234.5678), Px(1234.5678))
+            .translate(Px(1234.5678), Px(1234.5678))
+            .translate(Px(1234.5678), Px(1234.5678))
+            .translate(Px(1234.5678), Px(1234.5678))
+            .rotate(Deg(123.456));
+
+        assert!(long.to_string().len() > 128);
+        assert_eq!(css(long), long.to_string());
+    }
+
+    #[test]
+    fn css_values_style() {
+        use crate::css::{Px, Percent, Deg, Rgb, Rgba, Transform};
+
+        let _a: DomBuilder<HtmlElement> = DomBuilder::new_html("div")
+            .style("width", Px(50.0))
+            .style("height", Percent(100.0))
+            .style("color", Rgb(255, 0, 0))
+            .style("background-color", Rgba(255, 0, 0, 0.5))
+            .style("transform", Transform::new().translate(Px(10.0), Percent(20.0)).rotate(Deg(45.0)).scale(1.5))
+            .style_signal("left", always(Px(5.0)))
+            .style_signal("top", always(Some(Px(5.0))));
+    }
+
+    #[test]
+    fn form_bindings() {
+        use futures_signals::signal::Mutable;
+        use web_sys::{HtmlInputElement, HtmlTextAreaElement, HtmlSelectElement};
+
+        let _a = html!("input" => HtmlInputElement, {
+            .bind_value(Mutable::new(String::new()))
+        });
+
+        let _a = html!("input" => HtmlInputElement, {
+            .bind_number(Mutable::new(5.0))
+            .bind_checked(Mutable::new(false))
+        });
+
+        let size = Mutable::new(1u32);
+
+        let _a = html!("input" => HtmlInputElement, {
+            .attr("type", "radio")
+            .bind_radio(size.clone(), 1)
+        });
+
+        let _a = html!("input" => HtmlInputElement, {
+            .attr("type", "radio")
+            .bind_radio(size, 2)
+        });
+
+        let _a = html!("textarea" => HtmlTextAreaElement, {
+            .bind_value(Mutable::new(String::new()))
+        });
+
+        let _a = html!("select" => HtmlSelectElement, {
+            .bind_selected(Mutable::new(Some(5u32)))
+        });
+    }
+
+    #[test]
+    fn form_validation() {
+        use crate::forms::{Field, Form};
+        use web_sys::{HtmlInputElement, HtmlFormElement};
+
+        let name = Field::new(String::new())
+            .validator(|value: &String| if value.is_empty() { Err("Required".to_string()) } else { Ok(()) })
+            .async_validator(|_value: &String| async { Ok(()) });
+
+        let form = Form::new().field(&name);
+
+        let _a = html!("form" => HtmlFormElement, {
+            .form_submit(&form, |_| {})
+            .child(html!("input" => HtmlInputElement, {
+                .bind_field(&name)
+            }))
+            .child(html!("span", {
+                .text_signal(name.visible_error_signal().map(|error| error.unwrap_or_default()))
+            }))
+        });
+    }
+
+    #[test]
+    fn field_errors() {
+        use crate::forms::{Field, Form};
+
+        let name = Field::new(String::new())
+            .validator(|value: &String| if value.is_empty() { Err("Required".to_string()) } else { Ok(()) });
+
+        let form = Form::new().field(&name);
+
+        // The sync validators run eagerly, so the empty value is invalid before anything is validated
+        assert_eq!(name.error(), Some("Required".to_string()));
+        assert!(!name.is_valid());
+        assert!(!form.is_pending());
+        assert!(!form.is_valid());
+
+        drop(name.validate());
+        assert_eq!(name.error(), Some("Required".to_string()));
+        assert!(!form.is_valid());
+
+        // The async validators haven't run yet, so the field isn't valid
+        let email = Field::new("foo@bar".to_string())
+            .async_validator(|_value: &String| async { Ok(()) });
+
+        assert_eq!(email.error(), None);
+        assert!(!email.is_valid());
+        assert!(!Form::new().field(&email).is_valid());
+
+        // A field without validators is always valid
+        assert!(Field::new(5).is_valid());
+
+        name.value().set("foo".to_string());
+        drop(name.validate());
+        assert_eq!(name.error(), None);
+        assert!(form.is_valid());
+    }
+
+    #[test]
+    fn node_ref() {
+        use web_sys::HtmlInputElement;
+        use super::NodeRef;
+
+        let input = NodeRef::new();
+
+        let _a = html!("input" => HtmlInputElement, {
+            .node_ref(&input)
+        });
+
+        let _b: Option<HtmlInputElement> = input.get();
+        let _c = input.is_some_signal();
+    }
+
+    #[test]
+    fn context() {
+        use crate::context::ContextValue;
+
+        #[derive(Clone)]
+        struct User(&'static str);
+
         let _a = html!("div", {
-            .shadow_root!(ShadowRootMode::Closed => {
-                .children(&mut [
-                    html!("span")
-                ])
+            .provide_context(User("foo"))
+            .provide_context_signal(1u32, always(2u32))
+            .child(html!("span", {
+                .with_context(|dom, user: ContextValue<User>| {
+                    dom.text_signal(user.signal_ref(|user| user.as_ref().map(|user| user.0).unwrap_or("")))
+                })
+            }))
+        });
+    }
+
+    #[test]
+    fn component() {
+        use std::rc::Rc;
+        use futures_signals::signal::Mutable;
+        use crate::component::{Component, component};
+        use super::Dom;
+
+        struct Counter {
+            count: Mutable<i32>,
+        }
+
+        impl Component for Counter {
+            type Props = i32;
+
+            fn new(initial: i32) -> Self {
+                Self { count: Mutable::new(initial) }
+            }
+
+            fn render(this: &Rc<Self>) -> Dom {
+                html!("span", {
+                    .text_signal(this.count.signal().map(|count| count.to_string()))
+                })
+            }
+
+            fn mounted(this: &Rc<Self>) {
+                this.count.set(0);
+            }
+        }
+
+        let _a = html!("div", {
+            .child(component::<Counter>(5))
+        });
+    }
+
+    #[test]
+    fn custom_element() {
+        use wasm_bindgen::JsValue;
+        use crate::events;
+        use crate::custom_element::CustomElement;
+
+        CustomElement::new("my-counter")
+            .observed_attributes(&["label"])
+            .properties(&["count"])
+            .shadow_root(ShadowRootMode::Open)
+            .define(|context| {
+                html!("button", {
+                    .text_signal(context.attribute_signal("label").map(|label| label.unwrap_or_default()))
+                    .text_signal(context.property_signal("count").map(|count| count.as_f64().unwrap_or(0.0).to_string()))
+                    .event(clone!(context => move |_: events::Click| {
+                        context.dispatch_event("increment", &JsValue::UNDEFINED);
+                    }))
+                })
+            });
+
+        // The render function synchronously creates another element with the same name,
+        // so connectedCallback is called recursively while the parent is being rendered
+        CustomElement::new("tree-node")
+            .define(|context| {
+                let depth: u32 = context.element().get_attribute("depth")
+                    .and_then(|depth| depth.parse().ok())
+                    .unwrap_or(0);
+
+                html!("div", {
+                    .apply_if(depth > 0, |dom| {
+                        dom.child(html!("tree-node", {
+                            .attr("depth", &(depth - 1).to_string())
+                        }))
+                    })
+                })
+            });
+    }
+
+    #[test]
+    fn slot() {
+        use web_sys::HtmlSlotElement;
+        use crate::slot::Slot;
+
+        let header = Slot::new();
+
+        let _a = html!("div", {
+            .shadow_root!(ShadowRootMode::Open => {
+                .child(html!("header", {
+                    .visible_signal(header.has_assigned_signal())
+                    .child(html!("slot" => HtmlSlotElement, {
+                        .attr("name", "header")
+                        .slot_ref(&header)
+                    }))
+                }))
+
+                .child(html!("slot" => HtmlSlotElement, {}))
+            })
+
+            .child(html!("h1", {
+                .slot("header")
+                .text("Title")
+            }))
+        });
+
+        let _b = header.assigned_elements_signal().map(|elements| elements.len());
+    }
+
+    #[test]
+    fn typed_elements() {
+        use futures_signals::signal::Mutable;
+        use web_sys::{HtmlInputElement, HtmlAnchorElement};
+        use crate::elements;
+
+        let done = Mutable::new(false);
+        let name = Mutable::new("foo".to_string());
+
+        let _a: DomBuilder<HtmlInputElement> = elements::input()
+            .type_("checkbox")
+            .checked_signal(done.signal());
+
+        let _b: DomBuilder<HtmlAnchorElement> = elements::a().href("/foo");
+
+        let _c = element!(div, {
+            .child(element!(input, {
+                .type_("text")
+                .value_signal(name.signal_cloned())
+                .disabled(true)
+                .pattern(String::from("[a-z]+"))
+                .max_length(10)
+                .autocomplete("name")
+            }))
+            .child(element!(progress, {
+                .value(0.5)
+                .max(1.0)
+            }))
+            .child(element!(section))
+            .child(element!(blockquote, { .cite("https://example.com") }))
+            .child(element!(time, { .date_time("2020-01-01") }))
+            .child(element!(video, {
+                .child(element!(source, {
+                    .src("/foo.webm")
+                    .type_("video/webm")
+                }))
+                .child(element!(track, {
+                    .kind("captions")
+                    .default(true)
+                }))
+            }))
+        });
+    }
+
+    #[test]
+    fn spring_animation() {
+        use crate::animation::{Spring, SpringAnimation, Clock, set_clock, advance_clock, set_prefers_reduced_motion};
+
+        set_clock(Clock::Manual);
+
+        // The operating system's setting can't be read outside of the browser
+        set_prefers_reduced_motion(Some(false));
+
+        let spring = SpringAnimation::new(Spring::WOBBLY, 0.0);
+
+        spring.animate_to(100.0);
+        advance_clock(32.0);
+
+        let value = spring.current();
+        let velocity = spring.velocity();
+        assert!(value > 0.0 && value < 100.0);
+        assert!(velocity > 0.0);
+
+        // Changing the target keeps the velocity, so it keeps moving forward for a moment
+        spring.animate_to(-100.0);
+        assert_eq!(spring.velocity(), velocity);
+        assert_eq!(spring.target(), -100.0);
+
+        advance_clock(1.0);
+        assert!(spring.current() > value);
+
+        // It settles exactly on the target once it is within the tolerance
+        for _ in 0..1000 {
+            advance_clock(16.0);
+
+            if spring.velocity() == 0.0 {
+                break;
+            }
+        }
+
+        assert_eq!(spring.current(), -100.0);
+        assert_eq!(spring.velocity(), 0.0);
+
+        advance_clock(16.0);
+        assert_eq!(spring.current(), -100.0);
+
+        // A long frame (e.g. when the page was in the background) only moves the spring by 64ms
+        let long = SpringAnimation::new(Spring::WOBBLY, 0.0);
+        long.animate_to(100.0);
+        advance_clock(1000.0);
+
+        let (value, velocity) = (long.current(), long.velocity());
+        assert!(value > 0.0 && value < 100.0);
+
+        let clamped = SpringAnimation::new(Spring::WOBBLY, 0.0);
+        clamped.animate_to(100.0);
+        advance_clock(64.0);
+
+        assert_eq!(clamped.current(), value);
+        assert_eq!(clamped.velocity(), velocity);
+    }
+
+    #[test]
+    fn timeline() {
+        use futures_util::FutureExt;
+        use crate::animation::{MutableAnimation, Percentage, Timeline, Clock, set_clock, advance_clock, set_prefers_reduced_motion};
+
+        set_clock(Clock::Manual);
+
+        // The operating system's setting can't be read outside of the browser
+        set_prefers_reduced_motion(Some(false));
+
+        let fade = MutableAnimation::new(300.0);
+        let slide = MutableAnimation::new(500.0);
+        let items: Vec<MutableAnimation> = (0..10).map(|_| MutableAnimation::new(200.0)).collect();
+
+        let timeline = Timeline::new()
+            .then(&fade)
+            .with(&slide)
+            .delay(100.0)
+            .stagger(&items, 30.0);
+
+        // The items start at 600ms, each one 30ms after the previous one
+        assert_eq!(timeline.duration(), 600.0 + (9.0 * 30.0) + 200.0);
+
+        timeline.seek(250.0);
+        assert_eq!(fade.current_percentage(), Percentage::new(250.0 / 300.0));
+        assert_eq!(slide.current_percentage(), Percentage::new(0.5));
+        assert!(items.iter().all(|item| item.current_percentage() == Percentage::START));
+
+        timeline.seek(650.0);
+        assert_eq!(fade.current_percentage(), Percentage::END);
+        assert_eq!(slide.current_percentage(), Percentage::END);
+        assert_eq!(items[0].current_percentage(), Percentage::new(0.25));
+        assert_eq!(items[1].current_percentage(), Percentage::new(0.1));
+        assert_eq!(items[2].current_percentage(), Percentage::START);
+
+        let mut finished = Box::pin(timeline.finished());
+        assert_eq!((&mut finished).now_or_never(), None);
+
+        timeline.play();
+        advance_clock(300.0);
+        assert_eq!(timeline.time(), 950.0);
+        assert_eq!(items[9].current_percentage(), Percentage::new(0.4));
+        assert_eq!((&mut finished).now_or_never(), None);
+
+        advance_clock(300.0);
+        assert_eq!(timeline.time(), timeline.duration());
+        assert!(!timeline.is_playing());
+        assert!(items.iter().all(|item| item.current_percentage() == Percentage::END));
+        assert_eq!(finished.now_or_never(), Some(()));
+
+        // A delay before a parallel animation delays it
+        let first = MutableAnimation::new(100.0);
+        let second = MutableAnimation::new(100.0);
+
+        let timeline = Timeline::new()
+            .then(&first)
+            .delay(100.0)
+            .with(&second);
+
+        assert_eq!(timeline.duration(), 200.0);
+
+        timeline.seek(150.0);
+        assert_eq!(first.current_percentage(), Percentage::END);
+        assert_eq!(second.current_percentage(), Percentage::new(0.5));
+    }
+
+    #[test]
+    fn animate_to() {
+        use futures_util::FutureExt;
+        use crate::animation::{MutableAnimation, Percentage, Repeat, Clock, set_clock, advance_clock, set_prefers_reduced_motion};
+
+        set_clock(Clock::Manual);
+
+        // The operating system's setting can't be read outside of the browser
+        set_prefers_reduced_motion(Some(false));
+
+        let animation = MutableAnimation::new(300.0);
+
+        animation.set_repeat(Repeat::Times(3));
+        animation.set_yoyo(true);
+
+        let mut done = animation.animate_to(Percentage::END);
+        assert_eq!((&mut done).now_or_never(), None);
+
+        advance_clock(300.0);
+        assert_eq!(animation.current_percentage(), Percentage::END);
+        assert_eq!((&mut done).now_or_never(), None);
+
+        advance_clock(300.0);
+        assert_eq!(animation.current_percentage(), Percentage::START);
+        assert_eq!((&mut done).now_or_never(), None);
+
+        advance_clock(300.0);
+        assert_eq!(animation.current_percentage(), Percentage::END);
+        assert_eq!(done.now_or_never(), Some(true));
+        assert!(!animation.is_playing());
+
+        // Interrupting the animation resolves its future with false
+        let reversed = animation.reverse();
+        advance_clock(150.0);
+        assert_eq!(animation.current_percentage(), Percentage::new(0.5));
+
+        animation.jump_to(Percentage::END);
+        assert_eq!(reversed.now_or_never(), Some(false));
+
+        // Animating to the current target returns the finished future
+        assert_eq!(animation.animate_to(Percentage::END).now_or_never(), Some(true));
+    }
+
+    #[test]
+    fn easing() {
+        use crate::animation::easing::{self, CubicBezier, Linear, StepPosition};
+
+        fn assert_close(actual: Percentage, expected: f64) {
+            assert!((actual.into_f64() - expected).abs() < 1e-6, "{} != {}", actual.into_f64(), expected);
+        }
+
+        let ease = CubicBezier::ease_in_out();
+
+        let curves: &[fn(Percentage) -> Percentage] = &[
+            easing::quad,
+            easing::cubic,
+            easing::quart,
+            easing::quint,
+            easing::sine,
+            easing::expo,
+            easing::circ,
+            easing::back,
+            easing::elastic,
+            easing::bounce,
+            easing::ease,
+            easing::ease_in,
+            easing::ease_out,
+            easing::ease_in_out,
+            |p| easing::out(p, easing::bounce),
+            |p| easing::in_out(p, easing::elastic),
+        ];
+
+        for curve in curves {
+            assert_close(curve(Percentage::START), 0.0);
+            assert_close(curve(Percentage::END), 1.0);
+        }
+
+        assert_close(ease.easing(Percentage::START), 0.0);
+        assert_close(ease.easing(Percentage::END), 1.0);
+
+        // ease-in-out is symmetric around the middle
+        for i in 0..=10 {
+            let p = i as f64 / 10.0;
+            assert_close(ease.easing(Percentage::new(p)), 1.0 - ease.easing(Percentage::new(1.0 - p)).into_f64());
+        }
+
+        assert_close(easing::steps(Percentage::new(0.5), 4, StepPosition::JumpEnd), 0.5);
+        assert_close(easing::steps(Percentage::new(0.5), 4, StepPosition::JumpStart), 0.75);
+        assert_close(easing::steps(Percentage::new(0.1), 4, StepPosition::JumpEnd), 0.0);
+
+        // linear(0, 0.75 25%, 1)
+        let linear = Linear::with_stops(&[(0.0, None), (0.75, Some(0.25)), (1.0, None)]);
+        assert_close(linear.easing(Percentage::new(0.25)), 0.75);
+        assert_close(linear.easing(Percentage::new(0.625)), 0.875);
+
+        // back and elastic overshoot below 0.0
+        assert!(easing::back(Percentage::new(0.2)).into_f64() < 0.0);
+        assert!(easing::elastic(Percentage::new(0.9)).into_f64() < 0.0);
+        assert!(easing::out(Percentage::new(0.8), easing::back).into_f64() > 1.0);
+    }
+
+    #[test]
+    fn interpolate() {
+        use crate::animation::{Interpolate, Point};
+        use crate::css::{Px, Deg, Rgb, Rgba, Hsl, Oklab, Transform};
+
+        let half = Percentage::new(0.5);
+
+        assert_eq!(half.interpolate(&Px(0.0), &Px(100.0)), Px(50.0));
+        assert_eq!(Percentage::START.interpolate(&Px(0.0), &Px(100.0)), Px(0.0));
+        assert_eq!(Percentage::END.interpolate(&Px(0.0), &Px(100.0)), Px(100.0));
+
+        // Converting to Oklab and back loses at most 1 unit of precision
+        for color in [Rgb(0, 0, 0), Rgb(255, 255, 255), Rgb(255, 0, 0), Rgb(12, 200, 97), Rgb(128, 64, 250)] {
+            let Rgb(r, g, b) = Rgb::from(Oklab::from(color));
+            assert!(r.abs_diff(color.0) <= 1 && g.abs_diff(color.1) <= 1 && b.abs_diff(color.2) <= 1, "{} != {}", Rgb(r, g, b), color);
+        }
+
+        assert_eq!(Rgb(255, 0, 0).interpolate_oklab(&Rgb(0, 0, 255), Percentage::START), Rgb(255, 0, 0));
+        assert_eq!(Rgba(255, 0, 0, 1.0).interpolate_oklab(&Rgba(0, 0, 255, 0.5), Percentage::END), Rgba(0, 0, 255, 0.5));
+
+        // The hue takes the shortest path around the color wheel
+        assert_eq!(half.interpolate(&Hsl(350.0, 100.0, 50.0), &Hsl(30.0, 50.0, 50.0)), Hsl(10.0, 75.0, 50.0));
+        assert_eq!(half.interpolate(&Hsl(30.0, 100.0, 50.0), &Hsl(350.0, 100.0, 50.0)), Hsl(10.0, 100.0, 50.0));
+
+        let from = Transform::new().translate(Px(0.0), Px(0.0)).rotate(Deg(0.0));
+        let to = Transform::new().translate(Px(100.0), Px(50.0)).rotate(Deg(90.0));
+        assert_eq!(from.interpolate(&to, half).to_string(), "translate(50px, 25px) rotate(45deg)");
+
+        assert_eq!(Point::new(0.0, 0.0).interpolate(&Point::new(10.0, 20.0), half), Point::new(5.0, 10.0));
+
+        let tuple: (f64, [f32; 2]) = half.interpolate(&(0.0, [0.0, 1.0]), &(1.0, [2.0, 3.0]));
+        assert_eq!(tuple, (0.5, [1.0, 2.0]));
+    }
+
+    #[test]
+    fn child_signal_animated() {
+        use futures_signals::signal::Mutable;
+        use crate::animation::TransitionMode;
+
+        let route = Mutable::new(0);
+
+        let _a = html!("main", {
+            .child_signal_animated(300.0, TransitionMode::Sequential, route.signal().map(Some), |route: i32, animation| {
+                html!("div", {
+                    .style_signal("opacity", animation.signal().map(|t| t.into_f64().to_string()))
+                    .text(&route.to_string())
+                })
+            })
+            .child_signal_animated(300.0, TransitionMode::CrossFade, always(None), |_: (), _| {
+                html!("div", {})
             })
         });
     }
 
+    #[test]
+    fn children_signal_vec_flip() {
+        use futures_signals::signal_vec::{MutableVec, SignalVecExt};
+        use crate::animation::{AnimatedSignalVec, ReducedMotion};
+
+        let items = MutableVec::new_with_values(vec![1, 2, 3]);
+
+        let _a = html!("ul", {
+            .children_signal_vec_flip(300.0, items.signal_vec().animated_map(300.0, |item, animation| {
+                html!("li", {
+                    .style_signal("opacity", animation.signal().map(|t| t.into_f64().to_string()))
+                    .text(&item.to_string())
+                })
+            }))
+        });
+
+        let _b = html!("ul", {
+            .children_signal_vec_flip_reduced_motion(300.0, ReducedMotion::Scale(0.5), items.signal_vec().map(|item| {
+                html!("li", { .text(&item.to_string()) })
+            }))
+        });
+
+        items.lock_mut().move_from_to(0, 2);
+    }
+
+    #[test]
+    fn keyframe_property() {
+        use crate::web_animation::keyframe_property;
+
+        assert_eq!(keyframe_property("opacity"), "opacity");
+        assert_eq!(keyframe_property("background-color"), "backgroundColor");
+        assert_eq!(keyframe_property("border-top-left-radius"), "borderTopLeftRadius");
+        assert_eq!(keyframe_property("float"), "cssFloat");
+        assert_eq!(keyframe_property("--my-color"), "--my-color");
+    }
+
+    #[test]
+    fn web_animation() {
+        use web_sys::FillMode;
+        use crate::css::{Px, Rgb};
+        use crate::web_animation::{Keyframe, AnimationOptions};
+
+        // The CSS values are formatted when the keyframe is created
+        assert_eq!(
+            Keyframe::new().style("margin-left", Px(5.0)).style(String::from("color"), Rgb(255, 0, 0)),
+            Keyframe::new().style("margin-left", "5px").style("color", "rgb(255, 0, 0)"),
+        );
+
+        // Keyframes don't have fallbacks, so the first value is used
+        assert_eq!(
+            Keyframe::new().style("display", ["flex", "-webkit-flex"]),
+            Keyframe::new().style("display", "flex"),
+        );
+
+        let options = AnimationOptions {
+            duration: 300.0,
+            delay: 100.0,
+            end_delay: 50.0,
+            fill: FillMode::Forwards,
+            ..AnimationOptions::default()
+        };
+
+        // The ReducedMotion scale applies to the durations and delays, but not to the other options
+        assert_eq!(options.scaled(0.5), AnimationOptions {
+            duration: 150.0,
+            delay: 50.0,
+            end_delay: 25.0,
+            ..options.clone()
+        });
+
+        assert_eq!(options.scaled(0.0).duration, 0.0);
+        assert_eq!(options.scaled(1.0), options);
+    }
+
+    #[test]
+    fn web_animation_builder() {
+        use futures_signals::signal::Mutable;
+        use web_sys::FillMode;
+        use crate::animation::ReducedMotion;
+        use crate::web_animation::{Keyframe, AnimationOptions, WebAnimation};
+
+        let keyframes = [
+            Keyframe::new().style("opacity", "0").style("background-color", "red"),
+            Keyframe::new().offset(0.5).easing("ease-in"),
+            Keyframe::new().style("opacity", "1"),
+        ];
+
+        let options = AnimationOptions {
+            duration: 300.0,
+            fill: FillMode::Forwards,
+            reduced_motion: Some(ReducedMotion::Scale(0.5)),
+            ..AnimationOptions::default()
+        };
+
+        let animation = WebAnimation::new();
+        let open = Mutable::new(false);
+
+        let _a = html!("div", {
+            .animate(&keyframes, &options)
+            .animate_with(&animation, &keyframes, &options)
+            .animate_signal(open.signal(), &keyframes, &options)
+            .animate_signal_with(&animation, open.signal(), &keyframes, &options)
+        });
+
+        let _finished = animation.finished();
+    }
+
+    #[test]
+    fn reduced_motion() {
+        use futures_util::{FutureExt, StreamExt};
+        use crate::animation::{MutableAnimation, SpringAnimation, Spring, Timeline, ReducedMotion, Clock, set_clock, advance_clock, set_reduced_motion_policy, reduced_motion_policy, set_prefers_reduced_motion, prefers_reduced_motion};
+
+        set_clock(Clock::Manual);
+        set_prefers_reduced_motion(Some(true));
+
+        let mut prefers = prefers_reduced_motion().to_stream();
+        assert_eq!(prefers.next().now_or_never(), Some(Some(true)));
+
+        assert_eq!(reduced_motion_policy(), ReducedMotion::Instant);
+
+        // Instant jumps to the end
+        let animation = MutableAnimation::new(1000.0);
+        assert_eq!(animation.animate_to(Percentage::END).now_or_never(), Some(true));
+        assert_eq!(animation.current_percentage(), Percentage::END);
+
+        let spring = SpringAnimation::new(Spring::WOBBLY, 0.0);
+        spring.animate_to(100.0);
+        assert_eq!(spring.current(), 100.0);
+
+        // Scale(0.25) finishes in a quarter of the time
+        set_reduced_motion_policy(ReducedMotion::Scale(0.25));
+        assert_eq!(reduced_motion_policy(), ReducedMotion::Scale(0.25));
+
+        let mut done = animation.animate_to(Percentage::START);
+
+        advance_clock(125.0);
+        assert_eq!(animation.current_percentage(), Percentage::new(0.5));
+        assert_eq!((&mut done).now_or_never(), None);
+
+        advance_clock(125.0);
+        assert_eq!(animation.current_percentage(), Percentage::START);
+        assert_eq!(done.now_or_never(), Some(true));
+
+        // The global policy can be overridden for each animation
+        set_reduced_motion_policy(ReducedMotion::Instant);
+
+        animation.set_reduced_motion(Some(ReducedMotion::Ignore));
+        animation.animate_to(Percentage::END);
+
+        advance_clock(250.0);
+        assert_eq!(animation.current_percentage(), Percentage::new(0.25));
+
+        spring.set_reduced_motion(Some(ReducedMotion::Ignore));
+        spring.animate_to(0.0);
+        assert_eq!(spring.current(), 100.0);
+
+        let child = MutableAnimation::new(1000.0);
+        let timeline = Timeline::new().then(&child);
+        timeline.set_reduced_motion(Some(ReducedMotion::Scale(0.25)));
+        timeline.play();
+
+        advance_clock(125.0);
+        assert_eq!(timeline.time(), 500.0);
+        assert_eq!(child.current_percentage(), Percentage::new(0.5));
+
+        advance_clock(125.0);
+        assert_eq!(timeline.time(), 1000.0);
+        assert!(!timeline.is_playing());
+
+        // Without reduced motion, the policy doesn't matter
+        set_prefers_reduced_motion(Some(false));
+        assert_eq!(prefers.next().now_or_never(), Some(Some(false)));
+
+        let animation = MutableAnimation::new(1000.0);
+        animation.animate_to(Percentage::END);
+
+        advance_clock(250.0);
+        assert_eq!(animation.current_percentage(), Percentage::new(0.25));
+    }
+
+    #[test]
+    fn manual_clock() {
+        use crate::animation::{MutableAnimation, Percentage, Clock, set_clock, clock, advance_clock, pause_clock, resume_clock, is_clock_paused, set_prefers_reduced_motion};
+
+        set_clock(Clock::Manual);
+        assert_eq!(clock(), Clock::Manual);
+
+        // The operating system's setting can't be read outside of the browser
+        set_prefers_reduced_motion(Some(false));
+
+        let animation = MutableAnimation::new(1000.0);
+        animation.animate_to(Percentage::END);
+
+        advance_clock(250.0);
+        assert_eq!(animation.current_percentage(), Percentage::new(0.25));
+
+        pause_clock();
+        assert!(is_clock_paused());
+
+        advance_clock(250.0);
+        assert_eq!(animation.current_percentage(), Percentage::new(0.5));
+
+        resume_clock();
+
+        advance_clock(500.0);
+        assert_eq!(animation.current_percentage(), Percentage::END);
+        assert!(!animation.is_playing());
+    }
+
     #[test]
     fn with_cfg() {
         let _a = html!("div", {
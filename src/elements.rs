@@ -0,0 +1,524 @@
+//! Typed constructors for HTML elements.
+//!
+//! Each function creates a [`DomBuilder`] with the precise `web_sys` type of the element,
+//! and the element types have typed methods for their commonly used properties:
+//!
+//! ```rust
+//! use dominator::element;
+//!
+//! element!(input, {
+//!     .type_("checkbox")
+//!     .checked_signal(state.done.signal())
+//!     .disabled(true)
+//! })
+//! ```
+//!
+//! The [`element!`](crate::element) macro accepts the same block of methods as [`html!`](crate::html),
+//! but a misspelled element or property is a compile error instead of a runtime mistake.
+//!
+//! There is a constructor for every element in the [HTML Living Standard](https://html.spec.whatwg.org/multipage/indices.html#elements-3),
+//! with the `web_sys` type of the element's interface from the same table. Elements which don't have
+//! their own interface (such as `<section>`) create a [`DomBuilder<HtmlElement>`].
+//!
+//! The typed property methods don't cover every property (deprecated properties are left out, for example),
+//! the other properties can be set with [`DomBuilder::prop`] or [`DomBuilder::attr`].
+//!
+//! [`html!`](crate::html) always creates a [`DomBuilder<HtmlElement>`] unless a type is given (e.g. `html!("input" => HtmlInputElement)`),
+//! because changing the type of existing `html!("input")` calls would break code which relies on it being [`HtmlElement`].
+use futures_signals::signal::Signal;
+use web_sys::{
+    HtmlElement, HtmlAnchorElement, HtmlAreaElement, HtmlAudioElement, HtmlBaseElement, HtmlBodyElement,
+    HtmlBrElement, HtmlButtonElement, HtmlCanvasElement, HtmlDListElement, HtmlDataElement,
+    HtmlDataListElement, HtmlDetailsElement, HtmlDialogElement, HtmlDivElement, HtmlEmbedElement,
+    HtmlFieldSetElement, HtmlFormElement, HtmlHeadElement, HtmlHeadingElement, HtmlHrElement, HtmlHtmlElement,
+    HtmlIFrameElement, HtmlImageElement, HtmlInputElement, HtmlLabelElement, HtmlLegendElement, HtmlLiElement,
+    HtmlLinkElement, HtmlMapElement, HtmlMenuElement, HtmlMetaElement, HtmlMeterElement, HtmlModElement,
+    HtmlOListElement, HtmlObjectElement, HtmlOptGroupElement, HtmlOptionElement, HtmlOutputElement,
+    HtmlParagraphElement, HtmlPictureElement, HtmlPreElement, HtmlProgressElement, HtmlQuoteElement,
+    HtmlScriptElement, HtmlSelectElement, HtmlSlotElement, HtmlSourceElement, HtmlSpanElement,
+    HtmlStyleElement, HtmlTableCaptionElement, HtmlTableCellElement, HtmlTableColElement, HtmlTableElement,
+    HtmlTableRowElement, HtmlTableSectionElement, HtmlTemplateElement, HtmlTextAreaElement, HtmlTimeElement,
+    HtmlTitleElement, HtmlTrackElement, HtmlUListElement, HtmlVideoElement,
+};
+
+use crate::dom::DomBuilder;
+use crate::operations::for_each;
+use crate::traits::AsStr;
+
+
+macro_rules! elements {
+    ($($name:ident => $type:ident,)*) => {
+        $(
+            #[doc = concat!("Creates a [`<", stringify!($name), ">`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/", stringify!($name), ") element.")]
+            #[inline]
+            #[track_caller]
+            pub fn $name() -> DomBuilder<$type> {
+                DomBuilder::new_html(stringify!($name))
+            }
+        )*
+    };
+}
+
+macro_rules! element_prop {
+    ($name:ident, $signal:ident, str => $setter:ident) => {
+        #[inline]
+        pub fn $name<A>(self, value: A) -> Self where A: AsStr {
+            value.with_str(|value| self.element.$setter(value));
+            self
+        }
+
+        #[inline]
+        pub fn $signal<A, B>(mut self, value: B) -> Self where A: AsStr, B: Signal<Item = A> + 'static {
+            let element = self.element.clone();
+
+            self.callbacks.after_remove(for_each(value, move |value| {
+                value.with_str(|value| element.$setter(value));
+            }));
+
+            self
+        }
+    };
+    ($name:ident, $signal:ident, $kind:ty => $setter:ident) => {
+        #[inline]
+        pub fn $name(self, value: $kind) -> Self {
+            self.element.$setter(value);
+            self
+        }
+
+        #[inline]
+        pub fn $signal<A>(mut self, value: A) -> Self where A: Signal<Item = $kind> + 'static {
+            let element = self.element.clone();
+
+            self.callbacks.after_remove(for_each(value, move |value| {
+                element.$setter(value);
+            }));
+
+            self
+        }
+    };
+}
+
+macro_rules! element_props {
+    ($($type:ident { $($name:ident, $signal:ident: $kind:tt => $setter:ident;)* })*) => {
+        $(
+            impl DomBuilder<$type> {
+                $(element_prop!($name, $signal, $kind => $setter);)*
+            }
+        )*
+    };
+}
+
+
+// This is every element in the index of the HTML Living Standard
+// (https://html.spec.whatwg.org/multipage/indices.html#elements-3), except for the
+// foreign elements (`math` and `svg`), with the interface from the same table.
+elements! {
+    a => HtmlAnchorElement,
+    abbr => HtmlElement,
+    address => HtmlElement,
+    area => HtmlAreaElement,
+    article => HtmlElement,
+    aside => HtmlElement,
+    audio => HtmlAudioElement,
+    b => HtmlElement,
+    base => HtmlBaseElement,
+    bdi => HtmlElement,
+    bdo => HtmlElement,
+    blockquote => HtmlQuoteElement,
+    body => HtmlBodyElement,
+    br => HtmlBrElement,
+    button => HtmlButtonElement,
+    canvas => HtmlCanvasElement,
+    caption => HtmlTableCaptionElement,
+    cite => HtmlElement,
+    code => HtmlElement,
+    col => HtmlTableColElement,
+    colgroup => HtmlTableColElement,
+    data => HtmlDataElement,
+    datalist => HtmlDataListElement,
+    dd => HtmlElement,
+    del => HtmlModElement,
+    details => HtmlDetailsElement,
+    dfn => HtmlElement,
+    dialog => HtmlDialogElement,
+    div => HtmlDivElement,
+    dl => HtmlDListElement,
+    dt => HtmlElement,
+    em => HtmlElement,
+    embed => HtmlEmbedElement,
+    fieldset => HtmlFieldSetElement,
+    figcaption => HtmlElement,
+    figure => HtmlElement,
+    footer => HtmlElement,
+    form => HtmlFormElement,
+    h1 => HtmlHeadingElement,
+    h2 => HtmlHeadingElement,
+    h3 => HtmlHeadingElement,
+    h4 => HtmlHeadingElement,
+    h5 => HtmlHeadingElement,
+    h6 => HtmlHeadingElement,
+    head => HtmlHeadElement,
+    header => HtmlElement,
+    hgroup => HtmlElement,
+    hr => HtmlHrElement,
+    html => HtmlHtmlElement,
+    i => HtmlElement,
+    iframe => HtmlIFrameElement,
+    img => HtmlImageElement,
+    input => HtmlInputElement,
+    ins => HtmlModElement,
+    kbd => HtmlElement,
+    label => HtmlLabelElement,
+    legend => HtmlLegendElement,
+    li => HtmlLiElement,
+    link => HtmlLinkElement,
+    main => HtmlElement,
+    map => HtmlMapElement,
+    mark => HtmlElement,
+    menu => HtmlMenuElement,
+    meta => HtmlMetaElement,
+    meter => HtmlMeterElement,
+    nav => HtmlElement,
+    noscript => HtmlElement,
+    object => HtmlObjectElement,
+    ol => HtmlOListElement,
+    optgroup => HtmlOptGroupElement,
+    option => HtmlOptionElement,
+    output => HtmlOutputElement,
+    p => HtmlParagraphElement,
+    picture => HtmlPictureElement,
+    pre => HtmlPreElement,
+    progress => HtmlProgressElement,
+    q => HtmlQuoteElement,
+    rp => HtmlElement,
+    rt => HtmlElement,
+    ruby => HtmlElement,
+    s => HtmlElement,
+    samp => HtmlElement,
+    script => HtmlScriptElement,
+    search => HtmlElement,
+    section => HtmlElement,
+    select => HtmlSelectElement,
+    slot => HtmlSlotElement,
+    small => HtmlElement,
+    source => HtmlSourceElement,
+    span => HtmlSpanElement,
+    strong => HtmlElement,
+    style => HtmlStyleElement,
+    sub => HtmlElement,
+    summary => HtmlElement,
+    sup => HtmlElement,
+    table => HtmlTableElement,
+    tbody => HtmlTableSectionElement,
+    td => HtmlTableCellElement,
+    template => HtmlTemplateElement,
+    textarea => HtmlTextAreaElement,
+    tfoot => HtmlTableSectionElement,
+    th => HtmlTableCellElement,
+    thead => HtmlTableSectionElement,
+    time => HtmlTimeElement,
+    title => HtmlTitleElement,
+    tr => HtmlTableRowElement,
+    track => HtmlTrackElement,
+    u => HtmlElement,
+    ul => HtmlUListElement,
+    var => HtmlElement,
+    video => HtmlVideoElement,
+    wbr => HtmlElement,
+}
+
+
+element_props! {
+    HtmlAnchorElement {
+        href, href_signal: str => set_href;
+        target, target_signal: str => set_target;
+        rel, rel_signal: str => set_rel;
+        download, download_signal: str => set_download;
+        hreflang, hreflang_signal: str => set_hreflang;
+        ping, ping_signal: str => set_ping;
+        referrer_policy, referrer_policy_signal: str => set_referrer_policy;
+        type_, type_signal: str => set_type;
+    }
+
+    HtmlAreaElement {
+        alt, alt_signal: str => set_alt;
+        coords, coords_signal: str => set_coords;
+        download, download_signal: str => set_download;
+        href, href_signal: str => set_href;
+        rel, rel_signal: str => set_rel;
+        shape, shape_signal: str => set_shape;
+        target, target_signal: str => set_target;
+    }
+
+    HtmlAudioElement {
+        src, src_signal: str => set_src;
+        autoplay, autoplay_signal: bool => set_autoplay;
+        controls, controls_signal: bool => set_controls;
+        loop_, loop_signal: bool => set_loop;
+        muted, muted_signal: bool => set_muted;
+    }
+
+    HtmlBaseElement {
+        href, href_signal: str => set_href;
+        target, target_signal: str => set_target;
+    }
+
+    HtmlButtonElement {
+        disabled, disabled_signal: bool => set_disabled;
+        form_action, form_action_signal: str => set_form_action;
+        form_enctype, form_enctype_signal: str => set_form_enctype;
+        form_method, form_method_signal: str => set_form_method;
+        form_no_validate, form_no_validate_signal: bool => set_form_no_validate;
+        form_target, form_target_signal: str => set_form_target;
+        name, name_signal: str => set_name;
+        type_, type_signal: str => set_type;
+        value, value_signal: str => set_value;
+    }
+
+    HtmlCanvasElement {
+        width, width_signal: u32 => set_width;
+        height, height_signal: u32 => set_height;
+    }
+
+    HtmlDataElement {
+        value, value_signal: str => set_value;
+    }
+
+    HtmlDetailsElement {
+        open, open_signal: bool => set_open;
+    }
+
+    HtmlDialogElement {
+        open, open_signal: bool => set_open;
+    }
+
+    HtmlEmbedElement {
+        src, src_signal: str => set_src;
+        type_, type_signal: str => set_type;
+        width, width_signal: str => set_width;
+        height, height_signal: str => set_height;
+    }
+
+    HtmlFieldSetElement {
+        disabled, disabled_signal: bool => set_disabled;
+        name, name_signal: str => set_name;
+    }
+
+    HtmlFormElement {
+        accept_charset, accept_charset_signal: str => set_accept_charset;
+        action, action_signal: str => set_action;
+        autocomplete, autocomplete_signal: str => set_autocomplete;
+        enctype, enctype_signal: str => set_enctype;
+        method, method_signal: str => set_method;
+        name, name_signal: str => set_name;
+        no_validate, no_validate_signal: bool => set_no_validate;
+        target, target_signal: str => set_target;
+    }
+
+    HtmlIFrameElement {
+        src, src_signal: str => set_src;
+        srcdoc, srcdoc_signal: str => set_srcdoc;
+        name, name_signal: str => set_name;
+        referrer_policy, referrer_policy_signal: str => set_referrer_policy;
+        width, width_signal: str => set_width;
+        height, height_signal: str => set_height;
+    }
+
+    HtmlImageElement {
+        src, src_signal: str => set_src;
+        srcset, srcset_signal: str => set_srcset;
+        sizes, sizes_signal: str => set_sizes;
+        alt, alt_signal: str => set_alt;
+        decoding, decoding_signal: str => set_decoding;
+        is_map, is_map_signal: bool => set_is_map;
+        referrer_policy, referrer_policy_signal: str => set_referrer_policy;
+        use_map, use_map_signal: str => set_use_map;
+        width, width_signal: u32 => set_width;
+        height, height_signal: u32 => set_height;
+    }
+
+    HtmlInputElement {
+        accept, accept_signal: str => set_accept;
+        alt, alt_signal: str => set_alt;
+        autocomplete, autocomplete_signal: str => set_autocomplete;
+        checked, checked_signal: bool => set_checked;
+        disabled, disabled_signal: bool => set_disabled;
+        form_action, form_action_signal: str => set_form_action;
+        form_enctype, form_enctype_signal: str => set_form_enctype;
+        form_method, form_method_signal: str => set_form_method;
+        form_no_validate, form_no_validate_signal: bool => set_form_no_validate;
+        form_target, form_target_signal: str => set_form_target;
+        height, height_signal: u32 => set_height;
+        indeterminate, indeterminate_signal: bool => set_indeterminate;
+        input_mode, input_mode_signal: str => set_input_mode;
+        max, max_signal: str => set_max;
+        max_length, max_length_signal: i32 => set_max_length;
+        min, min_signal: str => set_min;
+        min_length, min_length_signal: i32 => set_min_length;
+        multiple, multiple_signal: bool => set_multiple;
+        name, name_signal: str => set_name;
+        pattern, pattern_signal: str => set_pattern;
+        placeholder, placeholder_signal: str => set_placeholder;
+        read_only, read_only_signal: bool => set_read_only;
+        required, required_signal: bool => set_required;
+        size, size_signal: u32 => set_size;
+        src, src_signal: str => set_src;
+        step, step_signal: str => set_step;
+        type_, type_signal: str => set_type;
+        value, value_signal: str => set_value;
+        width, width_signal: u32 => set_width;
+    }
+
+    HtmlLabelElement {
+        html_for, html_for_signal: str => set_html_for;
+    }
+
+    HtmlLinkElement {
+        as_, as_signal: str => set_as;
+        disabled, disabled_signal: bool => set_disabled;
+        href, href_signal: str => set_href;
+        hreflang, hreflang_signal: str => set_hreflang;
+        integrity, integrity_signal: str => set_integrity;
+        media, media_signal: str => set_media;
+        rel, rel_signal: str => set_rel;
+        type_, type_signal: str => set_type;
+    }
+
+    HtmlMapElement {
+        name, name_signal: str => set_name;
+    }
+
+    HtmlMetaElement {
+        content, content_signal: str => set_content;
+        http_equiv, http_equiv_signal: str => set_http_equiv;
+        name, name_signal: str => set_name;
+    }
+
+    HtmlMeterElement {
+        value, value_signal: f64 => set_value;
+        min, min_signal: f64 => set_min;
+        max, max_signal: f64 => set_max;
+    }
+
+    HtmlModElement {
+        cite, cite_signal: str => set_cite;
+        date_time, date_time_signal: str => set_date_time;
+    }
+
+    HtmlOListElement {
+        reversed, reversed_signal: bool => set_reversed;
+        start, start_signal: i32 => set_start;
+    }
+
+    HtmlObjectElement {
+        data, data_signal: str => set_data;
+        name, name_signal: str => set_name;
+        type_, type_signal: str => set_type;
+        width, width_signal: str => set_width;
+        height, height_signal: str => set_height;
+    }
+
+    HtmlOptGroupElement {
+        disabled, disabled_signal: bool => set_disabled;
+        label, label_signal: str => set_label;
+    }
+
+    HtmlOptionElement {
+        disabled, disabled_signal: bool => set_disabled;
+        label, label_signal: str => set_label;
+        selected, selected_signal: bool => set_selected;
+        value, value_signal: str => set_value;
+    }
+
+    HtmlOutputElement {
+        name, name_signal: str => set_name;
+        value, value_signal: str => set_value;
+    }
+
+    HtmlProgressElement {
+        value, value_signal: f64 => set_value;
+        max, max_signal: f64 => set_max;
+    }
+
+    HtmlQuoteElement {
+        cite, cite_signal: str => set_cite;
+    }
+
+    HtmlScriptElement {
+        async_, async_signal: bool => set_async;
+        defer, defer_signal: bool => set_defer;
+        integrity, integrity_signal: str => set_integrity;
+        no_module, no_module_signal: bool => set_no_module;
+        src, src_signal: str => set_src;
+        type_, type_signal: str => set_type;
+    }
+
+    HtmlSelectElement {
+        autocomplete, autocomplete_signal: str => set_autocomplete;
+        disabled, disabled_signal: bool => set_disabled;
+        multiple, multiple_signal: bool => set_multiple;
+        name, name_signal: str => set_name;
+        required, required_signal: bool => set_required;
+        size, size_signal: u32 => set_size;
+        value, value_signal: str => set_value;
+    }
+
+    HtmlSlotElement {
+        name, name_signal: str => set_name;
+    }
+
+    HtmlSourceElement {
+        media, media_signal: str => set_media;
+        sizes, sizes_signal: str => set_sizes;
+        src, src_signal: str => set_src;
+        srcset, srcset_signal: str => set_srcset;
+        type_, type_signal: str => set_type;
+    }
+
+    HtmlTableCellElement {
+        col_span, col_span_signal: u32 => set_col_span;
+        row_span, row_span_signal: u32 => set_row_span;
+    }
+
+    HtmlTableColElement {
+        span, span_signal: u32 => set_span;
+    }
+
+    HtmlTextAreaElement {
+        autocomplete, autocomplete_signal: str => set_autocomplete;
+        cols, cols_signal: u32 => set_cols;
+        disabled, disabled_signal: bool => set_disabled;
+        max_length, max_length_signal: i32 => set_max_length;
+        min_length, min_length_signal: i32 => set_min_length;
+        name, name_signal: str => set_name;
+        placeholder, placeholder_signal: str => set_placeholder;
+        read_only, read_only_signal: bool => set_read_only;
+        required, required_signal: bool => set_required;
+        rows, rows_signal: u32 => set_rows;
+        value, value_signal: str => set_value;
+        wrap, wrap_signal: str => set_wrap;
+    }
+
+    HtmlTimeElement {
+        date_time, date_time_signal: str => set_date_time;
+    }
+
+    HtmlTrackElement {
+        default, default_signal: bool => set_default;
+        kind, kind_signal: str => set_kind;
+        label, label_signal: str => set_label;
+        src, src_signal: str => set_src;
+        srclang, srclang_signal: str => set_srclang;
+    }
+
+    HtmlVideoElement {
+        src, src_signal: str => set_src;
+        autoplay, autoplay_signal: bool => set_autoplay;
+        controls, controls_signal: bool => set_controls;
+        loop_, loop_signal: bool => set_loop;
+        muted, muted_signal: bool => set_muted;
+        poster, poster_signal: str => set_poster;
+        width, width_signal: u32 => set_width;
+        height, height_signal: u32 => set_height;
+    }
+}
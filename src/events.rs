@@ -226,6 +226,16 @@ macro_rules! make_input_event {
     };
 }
 
+macro_rules! make_composition_event {
+    ($name:ident) => {
+        make_event!($name => web_sys::CompositionEvent);
+
+        impl $name {
+            #[inline] pub fn data(&self) -> Option<String> { self.event.data() }
+        }
+    };
+}
+
 macro_rules! make_animation_event {
     ($name:ident) => {
         make_event!($name => web_sys::AnimationEvent);
@@ -391,6 +401,16 @@ make_input_event!(BeforeInput);
 static_event_impl!(BeforeInput => "beforeinput");
 
 
+make_composition_event!(CompositionStart);
+static_event_impl!(CompositionStart => "compositionstart");
+
+make_composition_event!(CompositionUpdate);
+static_event_impl!(CompositionUpdate => "compositionupdate");
+
+make_composition_event!(CompositionEnd);
+static_event_impl!(CompositionEnd => "compositionend");
+
+
 make_animation_event!(AnimationStart);
 static_event_impl!(AnimationStart => "animationstart");
 
@@ -423,12 +443,18 @@ static_event_impl!(ScrollEnd => "scrollend");
 make_event!(Submit => web_sys::Event);
 static_event_impl!(Submit => "submit");
 
+make_event!(Invalid => web_sys::Event);
+static_event_impl!(Invalid => "invalid");
+
 make_event!(Resize => web_sys::UiEvent);
 static_event_impl!(Resize => "resize");
 
 make_event!(SelectionChange => web_sys::Event);
 static_event_impl!(SelectionChange => "selectionchange");
 
+make_event!(SlotChange => web_sys::Event);
+static_event_impl!(SlotChange => "slotchange");
+
 
 
 impl Input {
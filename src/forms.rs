@@ -0,0 +1,1037 @@
+use std::rc::Rc;
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::str::FromStr;
+
+use discard::DiscardOnDrop;
+use futures_util::future::{FutureExt, LocalBoxFuture};
+use futures_signals::{map_ref, CancelableFutureHandle};
+use futures_signals::signal::{Mutable, Signal, SignalExt, always};
+use wasm_bindgen::JsCast;
+use web_sys::{EventTarget, HtmlInputElement, HtmlTextAreaElement, HtmlSelectElement, HtmlFormElement};
+
+use crate::dom::{DomBuilder, EventOptions};
+use crate::operations::{for_each, spawn_future};
+use crate::utils::on;
+use crate::events;
+
+
+/// An element which has a `value`, such as `<input>`, `<textarea>`, or `<select>`.
+///
+/// This is used by [`DomBuilder::bind_value`] and the other form bindings.
+pub trait FormValue: AsRef<EventTarget> + Clone + 'static {
+    fn value(&self) -> String;
+
+    fn set_value(&self, value: &str);
+
+    /// Returns the start and end of the text selection, if the element supports it.
+    #[inline]
+    fn selection(&self) -> Option<(u32, u32)> {
+        None
+    }
+
+    #[inline]
+    fn set_selection(&self, _start: u32, _end: u32) {}
+
+    /// Sets the error message for the [Constraint Validation API](https://developer.mozilla.org/en-US/docs/Web/HTML/Constraint_validation).
+    ///
+    /// An empty message means that the element is valid.
+    #[inline]
+    fn set_custom_validity(&self, _message: &str) {}
+
+    /// Returns the browser's error message for the element, or an empty string if it is valid.
+    #[inline]
+    fn validation_message(&self) -> String {
+        String::new()
+    }
+}
+
+impl FormValue for HtmlInputElement {
+    #[inline]
+    fn value(&self) -> String {
+        HtmlInputElement::value(self)
+    }
+
+    #[inline]
+    fn set_value(&self, value: &str) {
+        HtmlInputElement::set_value(self, value);
+    }
+
+    #[inline]
+    fn set_custom_validity(&self, message: &str) {
+        HtmlInputElement::set_custom_validity(self, message);
+    }
+
+    #[inline]
+    fn validation_message(&self) -> String {
+        HtmlInputElement::validation_message(self).unwrap_or_default()
+    }
+
+    #[inline]
+    fn selection(&self) -> Option<(u32, u32)> {
+        // Some input types (such as number) don't support selection, so this ignores the errors
+        let start = self.selection_start().ok()??;
+        let end = self.selection_end().ok()??;
+        Some((start, end))
+    }
+
+    #[inline]
+    fn set_selection(&self, start: u32, end: u32) {
+        let _ = self.set_selection_range(start, end);
+    }
+}
+
+impl FormValue for HtmlTextAreaElement {
+    #[inline]
+    fn value(&self) -> String {
+        HtmlTextAreaElement::value(self)
+    }
+
+    #[inline]
+    fn set_value(&self, value: &str) {
+        HtmlTextAreaElement::set_value(self, value);
+    }
+
+    #[inline]
+    fn set_custom_validity(&self, message: &str) {
+        HtmlTextAreaElement::set_custom_validity(self, message);
+    }
+
+    #[inline]
+    fn validation_message(&self) -> String {
+        HtmlTextAreaElement::validation_message(self).unwrap_or_default()
+    }
+
+    #[inline]
+    fn selection(&self) -> Option<(u32, u32)> {
+        let start = self.selection_start().ok()??;
+        let end = self.selection_end().ok()??;
+        Some((start, end))
+    }
+
+    #[inline]
+    fn set_selection(&self, start: u32, end: u32) {
+        let _ = self.set_selection_range(start, end);
+    }
+}
+
+impl FormValue for HtmlSelectElement {
+    #[inline]
+    fn value(&self) -> String {
+        HtmlSelectElement::value(self)
+    }
+
+    #[inline]
+    fn set_value(&self, value: &str) {
+        HtmlSelectElement::set_value(self, value);
+    }
+
+    #[inline]
+    fn set_custom_validity(&self, message: &str) {
+        HtmlSelectElement::set_custom_validity(self, message);
+    }
+
+    #[inline]
+    fn validation_message(&self) -> String {
+        HtmlSelectElement::validation_message(self).unwrap_or_default()
+    }
+}
+
+
+fn is_focused<A>(element: &A) -> bool where A: AsRef<EventTarget> {
+    let element: &EventTarget = element.as_ref();
+
+    crate::bindings::WINDOW.with(|window| {
+        window.document()
+            .and_then(|document| document.active_element())
+            .map(|active| active.unchecked_ref::<EventTarget>() == element)
+            .unwrap_or(false)
+    })
+}
+
+// TODO should this inline ?
+fn bind<A, T, P, F>(builder: &mut DomBuilder<A>, mutable: Mutable<T>, parse: P, format: F)
+    where A: FormValue,
+          T: PartialEq + Clone + 'static,
+          P: Fn(&str) -> Option<T> + 'static,
+          F: Fn(&T) -> String + 'static {
+
+    let parse = Rc::new(parse);
+    let is_composing = Rc::new(Cell::new(false));
+
+    let update = {
+        let element = builder.element.clone();
+        let mutable = mutable.clone();
+        let parse = parse.clone();
+
+        Rc::new(move || {
+            if let Some(value) = parse(&element.value()) {
+                let mut lock = mutable.lock_mut();
+
+                // This avoids notifying the signals if the value hasn't changed
+                if *lock != value {
+                    *lock = value;
+                }
+            }
+        })
+    };
+
+    let element: &EventTarget = builder.element.as_ref();
+    let options = EventOptions::default();
+
+    builder.callbacks.after_remove(on(element, &options, {
+        let update = update.clone();
+        let is_composing = is_composing.clone();
+
+        move |_: events::Input| {
+            // The value is updated when the composition ends, so partial IME input isn't sent to the Mutable
+            if !is_composing.get() {
+                update();
+            }
+        }
+    }));
+
+    // Some elements (such as <select> in older browsers) only fire the change event
+    builder.callbacks.after_remove(on(element, &options, {
+        let update = update.clone();
+
+        move |_: events::Change| {
+            update();
+        }
+    }));
+
+    builder.callbacks.after_remove(on(element, &options, {
+        let is_composing = is_composing.clone();
+
+        move |_: events::CompositionStart| {
+            is_composing.set(true);
+        }
+    }));
+
+    builder.callbacks.after_remove(on(element, &options, {
+        let is_composing = is_composing.clone();
+
+        move |_: events::CompositionEnd| {
+            is_composing.set(false);
+            update();
+        }
+    }));
+
+    let element = builder.element.clone();
+
+    builder.callbacks.after_remove(for_each(mutable.signal_cloned(), move |value| {
+        // Changing the value while composing would break the IME input
+        if is_composing.get() {
+            return;
+        }
+
+        // If the element already has the value then it isn't changed, this avoids feedback loops
+        // and it preserves the cursor position while the user is typing
+        if parse(&element.value()).as_ref() != Some(&value) {
+            let selection = if is_focused(&element) { element.selection() } else { None };
+
+            element.set_value(&format(&value));
+
+            if let Some((start, end)) = selection {
+                element.set_selection(start, end);
+            }
+        }
+    }));
+}
+
+
+impl<A> DomBuilder<A> where A: FormValue {
+    /// Keeps the element's `value` and the [`Mutable`] in sync.
+    ///
+    /// When the user changes the value, the [`Mutable`] is updated, and when the
+    /// [`Mutable`] is changed, the element's value is updated.
+    ///
+    /// The cursor position and IME composition are preserved while the user is typing.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let name = Mutable::new("".to_string());
+    ///
+    /// html!("input" => HtmlInputElement, {
+    ///     .bind_value(name.clone())
+    /// })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn bind_value(mut self, mutable: Mutable<String>) -> Self {
+        bind(&mut self, mutable, |value| Some(String::from(value)), |value| value.clone());
+        self
+    }
+
+    /// Keeps the element's `value` and the [`Mutable`] in sync, parsing the value as a number.
+    ///
+    /// If the value cannot be parsed (e.g. while the user is still typing) then the [`Mutable`] is not changed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let age = Mutable::new(18u32);
+    ///
+    /// html!("input" => HtmlInputElement, {
+    ///     .attr("type", "number")
+    ///     .bind_number(age.clone())
+    /// })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn bind_number<T>(mut self, mutable: Mutable<T>) -> Self
+        where T: FromStr + ToString + PartialEq + Clone + 'static {
+        bind(&mut self, mutable, |value| value.trim().parse().ok(), |value| value.to_string());
+        self
+    }
+}
+
+impl DomBuilder<HtmlSelectElement> {
+    /// Keeps the selected `<option>` and the [`Mutable`] in sync.
+    ///
+    /// The `value` of the `<option>` is parsed into `T`. An empty value (or no selected option) is `None`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let count = Mutable::new(None::<u32>);
+    ///
+    /// html!("select" => HtmlSelectElement, {
+    ///     .bind_selected(count.clone())
+    ///     .children(&mut [
+    ///         html!("option", { .attr("value", "") .text("None") }),
+    ///         html!("option", { .attr("value", "1") .text("One") }),
+    ///         html!("option", { .attr("value", "2") .text("Two") }),
+    ///     ])
+    /// })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn bind_selected<T>(mut self, mutable: Mutable<Option<T>>) -> Self
+        where T: FromStr + ToString + PartialEq + Clone + 'static {
+        bind(&mut self, mutable, |value| {
+            if value.is_empty() {
+                Some(None)
+
+            } else {
+                value.parse().ok().map(Some)
+            }
+        }, |value| {
+            match value {
+                Some(value) => value.to_string(),
+                None => String::new(),
+            }
+        });
+        self
+    }
+}
+
+impl DomBuilder<HtmlInputElement> {
+    /// Keeps the `checked` state of a checkbox and the [`Mutable`] in sync.
+    ///
+    /// This doesn't work with radio buttons, because a radio button doesn't fire a `change` event
+    /// when it is unchecked by another radio button, use [`bind_radio`](DomBuilder::bind_radio) instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let is_enabled = Mutable::new(false);
+    ///
+    /// html!("input" => HtmlInputElement, {
+    ///     .attr("type", "checkbox")
+    ///     .bind_checked(is_enabled.clone())
+    /// })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn bind_checked(mut self, mutable: Mutable<bool>) -> Self {
+        let element = self.element.clone();
+
+        self.callbacks.after_remove(on(&self.element, &EventOptions::default(), {
+            let mutable = mutable.clone();
+
+            move |_: events::Change| {
+                mutable.set_neq(element.checked());
+            }
+        }));
+
+        let element = self.element.clone();
+
+        self.callbacks.after_remove(for_each(mutable.signal(), move |value| {
+            // This avoids feedback loops
+            if element.checked() != value {
+                element.set_checked(value);
+            }
+        }));
+
+        self
+    }
+
+    /// Binds a radio button to a [`Mutable`] which is shared by every radio button in the group.
+    ///
+    /// When the radio button is checked, the [`Mutable`] is set to `value`. And the radio button is
+    /// checked when the [`Mutable`] is equal to `value`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[derive(Clone, Copy, PartialEq)]
+    /// enum Size { Small, Large }
+    ///
+    /// let size = Mutable::new(Size::Small);
+    ///
+    /// html!("div", {
+    ///     .children(&mut [
+    ///         html!("input" => HtmlInputElement, {
+    ///             .attr("type", "radio")
+    ///             .attr("name", "size")
+    ///             .bind_radio(size.clone(), Size::Small)
+    ///         }),
+    ///         html!("input" => HtmlInputElement, {
+    ///             .attr("type", "radio")
+    ///             .attr("name", "size")
+    ///             .bind_radio(size.clone(), Size::Large)
+    ///         }),
+    ///     ])
+    /// })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn bind_radio<T>(mut self, mutable: Mutable<T>, value: T) -> Self where T: PartialEq + Clone + 'static {
+        let value = Rc::new(value);
+
+        let element = self.element.clone();
+
+        self.callbacks.after_remove(on(&self.element, &EventOptions::default(), {
+            let mutable = mutable.clone();
+            let value = value.clone();
+
+            // This only fires when the radio button becomes checked
+            move |_: events::Change| {
+                if element.checked() {
+                    mutable.set_neq((*value).clone());
+                }
+            }
+        }));
+
+        let element = self.element.clone();
+
+        self.callbacks.after_remove(for_each(mutable.signal_ref(move |current| *current == *value), move |checked| {
+            // This avoids feedback loops
+            if element.checked() != checked {
+                element.set_checked(checked);
+            }
+        }));
+
+        self
+    }
+}
+
+
+type Validator<T> = Box<dyn Fn(&T) -> Result<(), String>>;
+type AsyncValidator<T> = Box<dyn Fn(&T) -> LocalBoxFuture<'static, Result<(), String>>>;
+
+struct FieldState<T> {
+    value: Mutable<T>,
+    // The error from the validators
+    error: Mutable<Option<String>>,
+    // The error from the browser's built-in validation (e.g. `required`), it is kept separate so
+    // that it isn't turned into a custom validity
+    native_error: Mutable<Option<String>>,
+    is_pending: Mutable<bool>,
+    // The sync validators run eagerly, but the async validators only run when the value changes or it is validated
+    is_validated: Mutable<bool>,
+    is_dirty: Mutable<bool>,
+    is_touched: Mutable<bool>,
+    validators: RefCell<Vec<Validator<T>>>,
+    async_validators: RefCell<Vec<AsyncValidator<T>>>,
+    // Replacing this cancels the previous async validation
+    async_handle: RefCell<Option<DiscardOnDrop<CancelableFutureHandle>>>,
+}
+
+impl<T> FieldState<T> where T: Clone + 'static {
+    fn current_error(&self) -> Option<String> {
+        self.error.get_cloned().or_else(|| self.native_error.get_cloned())
+    }
+
+    fn error_signal(&self) -> impl Signal<Item = Option<String>> {
+        map_ref! {
+            let error = self.error.signal_cloned(),
+            let native_error = self.native_error.signal_cloned() => {
+                error.clone().or_else(|| native_error.clone())
+            }
+        }
+    }
+
+    fn sync_error(&self, value: &T) -> Option<String> {
+        self.validators.borrow().iter().find_map(|validator| validator(value).err())
+    }
+
+    // Runs the sync validators on the current value, and runs all of the validators again if
+    // the async validators haven't run yet or if the field is invalid
+    fn check(self: &Rc<Self>) {
+        if self.is_pending.get() {
+            return;
+        }
+
+        let value = self.value.get_cloned();
+
+        if let Some(error) = self.sync_error(&value) {
+            self.error.set_neq(Some(error));
+
+        } else if !self.is_validated.get() || self.error.lock_ref().is_some() {
+            self.run_validators(&value);
+        }
+    }
+
+    fn run_validators(self: &Rc<Self>, value: &T) {
+        let _ = self.async_handle.borrow_mut().take();
+
+        self.is_validated.set_neq(true);
+
+        let error = self.sync_error(value);
+
+        // The async validators only run if the sync validators succeed
+        if error.is_some() || self.async_validators.borrow().is_empty() {
+            self.error.set_neq(error);
+            self.is_pending.set_neq(false);
+            return;
+        }
+
+        let futures: Vec<_> = self.async_validators.borrow().iter().map(|validator| validator(value)).collect();
+
+        self.is_pending.set_neq(true);
+
+        let state = self.clone();
+
+        let handle = spawn_future(async move {
+            let mut error = None;
+
+            for future in futures {
+                if let Err(e) = future.await {
+                    error = Some(e);
+                    break;
+                }
+            }
+
+            state.error.set_neq(error);
+            state.is_pending.set_neq(false);
+        });
+
+        *self.async_handle.borrow_mut() = Some(handle);
+    }
+}
+
+
+/// A form field with validation.
+///
+/// The validators run whenever the value changes, and the result is available with [`Field::error_signal`].
+///
+/// Use [`DomBuilder::bind_field`] to bind the field to an `<input>`, `<textarea>`, or `<select>`.
+///
+/// # Example
+///
+/// ```rust
+/// let email = Field::new(String::new())
+///     .validator(|value: &String| {
+///         if value.contains('@') {
+///             Ok(())
+///         } else {
+///             Err("Invalid email".to_string())
+///         }
+///     })
+///     .async_validator(|value: &String| {
+///         let value = value.clone();
+///         async move { check_email_is_unused(value).await }
+///     });
+///
+/// html!("input" => HtmlInputElement, {
+///     .bind_field(&email)
+/// })
+/// ```
+pub struct Field<T> {
+    state: Rc<FieldState<T>>,
+}
+
+impl<T> Clone for Field<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self { state: self.state.clone() }
+    }
+}
+
+impl<T> std::fmt::Debug for Field<T> where T: std::fmt::Debug {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Field")
+            .field("value", &self.state.value)
+            .field("error", &self.state.error)
+            .finish()
+    }
+}
+
+impl<T> Field<T> where T: Clone + 'static {
+    #[inline]
+    pub fn new(value: T) -> Self {
+        Self {
+            state: Rc::new(FieldState {
+                value: Mutable::new(value),
+                error: Mutable::new(None),
+                native_error: Mutable::new(None),
+                is_pending: Mutable::new(false),
+                is_validated: Mutable::new(true),
+                is_dirty: Mutable::new(false),
+                is_touched: Mutable::new(false),
+                validators: RefCell::new(vec![]),
+                async_validators: RefCell::new(vec![]),
+                async_handle: RefCell::new(None),
+            }),
+        }
+    }
+
+    /// Adds a validator which returns an error message if the value is invalid.
+    ///
+    /// The validator runs immediately on the current value, so a field with an invalid initial value is never valid.
+    pub fn validator<F>(self, f: F) -> Self where F: Fn(&T) -> Result<(), String> + 'static {
+        let error = f(&self.state.value.lock_ref()).err();
+
+        self.state.validators.borrow_mut().push(Box::new(f));
+
+        if error.is_some() {
+            self.state.error.set_neq(error);
+        }
+
+        self
+    }
+
+    /// Adds an async validator (e.g. one which checks with the server).
+    ///
+    /// The async validators only run after all of the sync validators succeed.
+    /// If the value changes while an async validator is running, it is cancelled.
+    ///
+    /// The field isn't valid until the async validators have run, which happens when the value changes,
+    /// or when [`Field::validate`] is called, or when the form is submitted.
+    #[inline]
+    pub fn async_validator<F, R>(self, f: F) -> Self
+        where F: Fn(&T) -> R + 'static,
+              R: Future<Output = Result<(), String>> + 'static {
+        self.state.async_validators.borrow_mut().push(Box::new(move |value| f(value).boxed_local()));
+        self.state.is_validated.set_neq(false);
+        self
+    }
+
+    #[inline]
+    pub fn value(&self) -> &Mutable<T> {
+        &self.state.value
+    }
+
+    /// The current error message, or `None` if the field is valid.
+    ///
+    /// The errors from the validators take priority over the errors from the browser's built-in validation.
+    #[inline]
+    pub fn error(&self) -> Option<String> {
+        self.state.current_error()
+    }
+
+    #[inline]
+    pub fn error_signal(&self) -> impl Signal<Item = Option<String>> {
+        self.state.error_signal()
+    }
+
+    /// Like [`Field::error_signal`] except it is always `None` until the field is dirty or touched.
+    ///
+    /// This is useful to avoid showing errors before the user has interacted with the field.
+    #[inline]
+    pub fn visible_error_signal(&self) -> impl Signal<Item = Option<String>> {
+        map_ref! {
+            let error = self.state.error_signal(),
+            let is_dirty = self.state.is_dirty.signal(),
+            let is_touched = self.state.is_touched.signal() => {
+                if *is_dirty || *is_touched {
+                    error.clone()
+
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Whether the async validators are currently running.
+    #[inline]
+    pub fn is_pending_signal(&self) -> impl Signal<Item = bool> {
+        self.state.is_pending.signal()
+    }
+
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        self.state.is_pending.get()
+    }
+
+    /// Whether the validators have run and succeeded.
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.state.is_validated.get() && !self.state.is_pending.get() && self.state.current_error().is_none()
+    }
+
+    #[inline]
+    pub fn is_valid_signal(&self) -> impl Signal<Item = bool> {
+        map_ref! {
+            let is_validated = self.state.is_validated.signal(),
+            let is_pending = self.state.is_pending.signal(),
+            let is_error = self.state.error_signal().map(|error| error.is_some()) => {
+                *is_validated && !*is_pending && !*is_error
+            }
+        }
+    }
+
+    /// Whether the user has changed the value.
+    #[inline]
+    pub fn is_dirty_signal(&self) -> impl Signal<Item = bool> {
+        self.state.is_dirty.signal()
+    }
+
+    /// Whether the user has focused and then unfocused the field.
+    #[inline]
+    pub fn is_touched_signal(&self) -> impl Signal<Item = bool> {
+        self.state.is_touched.signal()
+    }
+
+    /// Marks the field as touched, which is useful to show all of the errors when submitting.
+    #[inline]
+    pub fn touch(&self) {
+        self.state.is_touched.set_neq(true);
+    }
+
+    /// Runs all of the validators on the current value, and returns whether the field is valid.
+    pub fn validate(&self) -> impl Future<Output = bool> {
+        let state = self.state.clone();
+
+        let value = state.value.get_cloned();
+        state.run_validators(&value);
+
+        async move {
+            state.is_pending.signal().wait_for(false).await;
+            state.current_error().is_none()
+        }
+    }
+
+    // Waits for the async validators which are already running, without running the validators again
+    fn settled(&self) -> impl Future<Output = bool> {
+        let state = self.state.clone();
+
+        async move {
+            state.is_pending.signal().wait_for(false).await;
+            state.current_error().is_none()
+        }
+    }
+}
+
+
+trait AnyField {
+    fn touch(&self);
+    fn check(&self);
+    fn is_pending(&self) -> bool;
+    fn is_valid(&self) -> bool;
+    fn validate(&self) -> LocalBoxFuture<'static, bool>;
+    fn settled(&self) -> LocalBoxFuture<'static, bool>;
+    fn is_valid_signal(&self) -> Box<dyn Signal<Item = bool> + Unpin>;
+}
+
+impl<T> AnyField for Field<T> where T: Clone + 'static {
+    #[inline]
+    fn touch(&self) {
+        Field::touch(self);
+    }
+
+    #[inline]
+    fn check(&self) {
+        self.state.check();
+    }
+
+    #[inline]
+    fn is_pending(&self) -> bool {
+        Field::is_pending(self)
+    }
+
+    #[inline]
+    fn is_valid(&self) -> bool {
+        Field::is_valid(self)
+    }
+
+    #[inline]
+    fn validate(&self) -> LocalBoxFuture<'static, bool> {
+        Field::validate(self).boxed_local()
+    }
+
+    #[inline]
+    fn settled(&self) -> LocalBoxFuture<'static, bool> {
+        Field::settled(self).boxed_local()
+    }
+
+    #[inline]
+    fn is_valid_signal(&self) -> Box<dyn Signal<Item = bool> + Unpin> {
+        Box::new(Field::is_valid_signal(self))
+    }
+}
+
+
+/// A group of [`Field`]s which are validated together.
+///
+/// Use [`DomBuilder::form_submit`] to prevent the `<form>` from being submitted while it is invalid.
+#[derive(Clone, Default)]
+pub struct Form {
+    fields: Rc<RefCell<Vec<Box<dyn AnyField>>>>,
+}
+
+impl std::fmt::Debug for Form {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Form")
+            .field("fields", &self.fields.borrow().len())
+            .finish()
+    }
+}
+
+impl Form {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a field to the form.
+    #[inline]
+    pub fn field<T>(self, field: &Field<T>) -> Self where T: Clone + 'static {
+        self.fields.borrow_mut().push(Box::new(field.clone()));
+        self
+    }
+
+    /// Whether any of the fields are running async validators.
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        self.fields.borrow().iter().any(|field| field.is_pending())
+    }
+
+    /// Whether all of the fields are valid.
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.fields.borrow().iter().all(|field| field.is_valid())
+    }
+
+    /// Whether all of the fields are valid.
+    ///
+    /// This only includes the fields which were added before this method was called.
+    pub fn is_valid_signal(&self) -> impl Signal<Item = bool> {
+        self.fields.borrow().iter().fold(Box::new(always(true)) as Box<dyn Signal<Item = bool> + Unpin>, |all, field| {
+            let field = field.is_valid_signal();
+
+            Box::new(map_ref! {
+                let all = all,
+                let field = field => {
+                    *all && *field
+                }
+            })
+        })
+    }
+
+    /// Marks all of the fields as touched.
+    #[inline]
+    pub fn touch(&self) {
+        for field in self.fields.borrow().iter() {
+            field.touch();
+        }
+    }
+
+    /// Runs all of the validators, and returns whether all of the fields are valid.
+    pub fn validate(&self) -> impl Future<Output = bool> {
+        let futures: Vec<_> = self.fields.borrow().iter().map(|field| field.validate()).collect();
+
+        async move {
+            let mut is_valid = true;
+
+            for future in futures {
+                is_valid = future.await && is_valid;
+            }
+
+            is_valid
+        }
+    }
+
+    // Runs the validators which haven't run on the current value yet
+    fn check(&self) {
+        for field in self.fields.borrow().iter() {
+            field.check();
+        }
+    }
+
+    // Waits for the async validators which are already running, and returns whether all of the fields are valid
+    fn settled(&self) -> impl Future<Output = bool> {
+        let futures: Vec<_> = self.fields.borrow().iter().map(|field| field.settled()).collect();
+
+        async move {
+            let mut is_valid = true;
+
+            for future in futures {
+                is_valid = future.await && is_valid;
+            }
+
+            is_valid
+        }
+    }
+}
+
+
+// Returns the browser's built-in validation message, without the custom validity message
+fn native_validation_message<A>(element: &A, custom: Option<&str>) -> Option<String> where A: FormValue {
+    element.set_custom_validity("");
+    let message = element.validation_message();
+    element.set_custom_validity(custom.unwrap_or(""));
+
+    if message.is_empty() {
+        None
+
+    } else {
+        Some(message)
+    }
+}
+
+
+impl<A> DomBuilder<A> where A: FormValue {
+    /// Binds the [`Field`] to the element, similar to [`DomBuilder::bind_value`].
+    ///
+    /// It also does the following:
+    ///
+    /// * Runs the validators whenever the value changes.
+    ///
+    /// * Marks the field as dirty when the user changes the value, and touched when the element loses focus.
+    ///
+    /// * Sets the element's [custom validity](https://developer.mozilla.org/en-US/docs/Web/API/HTMLInputElement/setCustomValidity)
+    ///   to the validators' error message, so it works with the `:invalid` CSS selector and the browser's form validation.
+    ///
+    /// * If the browser's built-in validation fails (e.g. `required` or `type="email"`) then the field's error is set
+    ///   to the browser's message. It is cleared when the value becomes valid.
+    #[inline]
+    #[track_caller]
+    pub fn bind_field(mut self, field: &Field<String>) -> Self {
+        let state = field.state.clone();
+
+        bind(&mut self, state.value.clone(), |value| Some(String::from(value)), |value| value.clone());
+
+        let element: &EventTarget = self.element.as_ref();
+        let options = EventOptions::default();
+
+        self.callbacks.after_remove(on(element, &options, {
+            let state = state.clone();
+            let element = self.element.clone();
+
+            move |_: events::Input| {
+                state.is_dirty.set_neq(true);
+
+                // The browser's error is only shown after the element was invalid, and it is updated as the user types
+                if state.native_error.lock_ref().is_some() {
+                    let message = native_validation_message(&element, state.error.lock_ref().as_deref());
+                    state.native_error.set_neq(message);
+                }
+            }
+        }));
+
+        self.callbacks.after_remove(on(element, &options, {
+            let state = state.clone();
+
+            move |_: events::Blur| {
+                state.is_touched.set_neq(true);
+            }
+        }));
+
+        self.callbacks.after_remove(on(element, &options, {
+            let state = state.clone();
+            let element = self.element.clone();
+
+            move |_: events::Invalid| {
+                state.is_touched.set_neq(true);
+
+                let message = native_validation_message(&element, state.error.lock_ref().as_deref());
+                state.native_error.set_neq(message);
+            }
+        }));
+
+        self.callbacks.after_remove(for_each(state.value.signal_cloned(), {
+            let state = state.clone();
+
+            move |value| {
+                state.run_validators(&value);
+            }
+        }));
+
+        let element = self.element.clone();
+
+        self.callbacks.after_remove(for_each(state.error.signal_cloned(), move |error| {
+            element.set_custom_validity(error.as_deref().unwrap_or(""));
+        }));
+
+        self
+    }
+}
+
+impl DomBuilder<HtmlFormElement> {
+    /// Calls `on_submit` when the `<form>` is submitted, but only if the [`Form`] is valid.
+    ///
+    /// If the form is invalid then the submit is prevented and all of the fields are marked as touched.
+    ///
+    /// If the form is still running async validators then the submit is delayed until they finish,
+    /// and then the form is submitted again if it is valid.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// html!("form" => HtmlFormElement, {
+    ///     .form_submit(&form, |e| {
+    ///         e.prevent_default();
+    ///         send_to_server();
+    ///     })
+    /// })
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn form_submit<F>(mut self, form: &Form, mut on_submit: F) -> Self where F: FnMut(events::Submit) + 'static {
+        let form = form.clone();
+        let element = self.element.clone();
+
+        // This is dropped when the element is removed, which cancels the queued submit
+        let queued: RefCell<Option<DiscardOnDrop<CancelableFutureHandle>>> = RefCell::new(None);
+
+        self.callbacks.after_remove(on(&self.element, &EventOptions::preventable(), move |e: events::Submit| {
+            // The value might have changed without the validators running (e.g. it wasn't bound to an element)
+            form.check();
+
+            if form.is_valid() {
+                let _ = queued.borrow_mut().take();
+                on_submit(e);
+
+            } else if form.is_pending() {
+                e.prevent_default();
+
+                let form = form.clone();
+                let element = element.clone();
+
+                *queued.borrow_mut() = Some(spawn_future(async move {
+                    if form.settled().await {
+                        // This fires the submit event again, which now calls on_submit
+                        let _ = element.request_submit();
+
+                    } else {
+                        form.touch();
+                        element.report_validity();
+                    }
+                }));
+
+            } else {
+                let _ = queued.borrow_mut().take();
+                e.prevent_default();
+                form.touch();
+                element.report_validity();
+            }
+        }));
+
+        self
+    }
+}
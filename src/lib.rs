@@ -23,3 +23,12 @@ pub mod traits;
 pub mod animation;
 pub mod routing;
 pub mod events;
+pub mod theme;
+pub mod css;
+pub mod forms;
+pub mod context;
+pub mod component;
+pub mod custom_element;
+pub mod slot;
+pub mod elements;
+pub mod web_animation;
@@ -1,5 +1,5 @@
 #[cfg(doc)]
-use crate::{DomBuilder, Dom, StylesheetBuilder, ClassBuilder};
+use crate::{DomBuilder, Dom, AdoptedStylesheet, StylesheetBuilder, ClassBuilder, DynamicClass, FontFaceBuilder, CustomPropertyBuilder};
 
 
 #[doc(hidden)]
@@ -300,6 +300,12 @@ macro_rules! with_cfg {
 ///
 /// The method calls are applied to the shadow root, not the parent [`DomBuilder`].
 ///
+/// Styles from [`class!`](crate::class) and [`stylesheet!`](crate::stylesheet) don't apply inside of
+/// the shadow root, use [`AdoptedStylesheet`] if you want to style the shadow root.
+///
+/// Use a `<slot>` element to project the parent's children into the shadow root, and use
+/// [`Slot`](crate::slot::Slot) if you want to observe which elements are assigned to the `<slot>`.
+///
 /// The block uses the [`apply_methods!`] macro, see the docs for [`apply_methods!`] for more details.
 #[macro_export]
 macro_rules! shadow_root {
@@ -344,6 +350,35 @@ macro_rules! html {
 }
 
 
+/// Creates an HTML [`Dom`] node with a typed element constructor from [`elements`](crate::elements).
+///
+/// The first argument is the name of the element, and the second argument is a block of method calls:
+///
+/// ```rust
+/// element!(input, {
+///     .type_("text")
+///     .placeholder("Name")
+///     .value_signal(state.name.signal_cloned())
+/// })
+/// ```
+///
+/// Unlike [`html!`], the [`DomBuilder`] has the precise `web_sys` type of the element
+/// (e.g. [`web_sys::HtmlInputElement`]), so you can use the typed property methods.
+///
+/// The block uses the [`apply_methods!`] macro, see the docs for [`apply_methods!`] for more details.
+#[macro_export]
+macro_rules! element {
+    ($name:ident) => {
+        $crate::element!($name, {})
+    };
+    ($name:ident, { $($methods:tt)* }) => {{
+        let builder = $crate::elements::$name();
+        let output = $crate::apply_methods!(builder, { $($methods)* });
+        $crate::DomBuilder::into_dom(output)
+    }};
+}
+
+
 /// Creates an SVG [`Dom`] node.
 ///
 /// The first argument is the [SVG tag](https://developer.mozilla.org/en-US/docs/Web/SVG/Element), and the second argument is a block of method calls.
@@ -494,10 +529,10 @@ macro_rules! stylesheet {
 #[macro_export]
 macro_rules! class {
     (#![prefix = $name:literal] $($methods:tt)*) => {{
-        $crate::ClassBuilder::__internal_done($crate::apply_methods!($crate::ClassBuilder::__internal_new(Some($name)), { $($methods)* }))
+        $crate::ClassBuilder::__internal_done($crate::apply_methods!($crate::ClassBuilder::__internal_new(Some($name), module_path!()), { $($methods)* }))
     }};
     ($($methods:tt)*) => {{
-        $crate::ClassBuilder::__internal_done($crate::apply_methods!($crate::ClassBuilder::__internal_new(None), { $($methods)* }))
+        $crate::ClassBuilder::__internal_done($crate::apply_methods!($crate::ClassBuilder::__internal_new(None, module_path!()), { $($methods)* }))
     }};
 }
 
@@ -539,12 +574,203 @@ macro_rules! pseudo {
         $crate::pseudo!($this, $rules, {})
     };
     ($this:ident, $rules:expr, { $($methods:tt)* }) => {{
-        $crate::stylesheet!($crate::__internal::Pseudo::new($crate::ClassBuilder::__internal_class_name(&$this), $rules), { $($methods)* });
+        let pseudo = $crate::apply_methods!($crate::ClassBuilder::__internal_pseudo(&$this, $rules), { $($methods)* });
+        $crate::ClassBuilder::__internal_pseudo_done($this, pseudo)
+    }};
+}
+
+
+/// The same as [`class!`] except it returns a [`DynamicClass`].
+///
+/// A normal [`class!`] lives forever, which is what you want for a `static` class. But if
+/// you create classes at runtime (for example based on user data) then you should use
+/// `dynamic_class!` instead, because it removes its CSS rules when it is no longer used:
+///
+/// ```rust
+/// fn color_class(color: &str) -> DynamicClass {
+///     dynamic_class! {
+///         .style("color", color)
+///         .pseudo!(":hover", {
+///             .style("text-decoration", "underline")
+///         })
+///     }
+/// }
+///
+/// html!("div", {
+///     .dynamic_class(&color_class("green"))
+/// })
+/// ```
+///
+/// A [`DynamicClass`] is reference-counted, when the last clone is dropped the class is removed.
+///
+/// If you create multiple dynamic classes with identical styles, they will share the same class.
+/// Classes which use signals (such as `style_signal`) are never shared.
+#[macro_export]
+macro_rules! dynamic_class {
+    (#![prefix = $name:literal] $($methods:tt)*) => {{
+        $crate::ClassBuilder::__internal_done_dynamic($crate::apply_methods!($crate::ClassBuilder::__internal_new_dynamic(Some($name)), { $($methods)* }))
+    }};
+    ($($methods:tt)*) => {{
+        $crate::ClassBuilder::__internal_done_dynamic($crate::apply_methods!($crate::ClassBuilder::__internal_new_dynamic(None), { $($methods)* }))
+    }};
+}
+
+
+/// Creates a locally scoped CSS [`@keyframes`](https://developer.mozilla.org/en-US/docs/Web/CSS/@keyframes) rule.
+///
+/// This is the same as [`class!`], except it creates an animation instead of a class.
+///
+/// The `keyframes!` macro accepts a block of method calls, and it uses the [`keyframe!`](crate::keyframe)
+/// macro to define each keyframe:
+///
+/// ```rust
+/// use dominator::animation::Percentage;
+///
+/// static FADE_IN: Lazy<String> = Lazy::new(|| keyframes! {
+///     .keyframe!(Percentage::START, {
+///         .style("opacity", "0")
+///     })
+///
+///     .keyframe!(Percentage::new(0.5), {
+///         .style("opacity", "0.8")
+///     })
+///
+///     .keyframe!(Percentage::END, {
+///         .style("opacity", "1")
+///     })
+/// });
+/// ```
+///
+/// The block uses the [`apply_methods!`] macro, see the docs for [`apply_methods!`] for more details.
+///
+/// The `keyframes!` macro returns a `String`, which is a unique animation name. You can then use that
+/// name in a [`DomBuilder`] or [`ClassBuilder`]:
+///
+/// ```rust
+/// html!("div", {
+///     .style("animation", format!("{} 300ms ease-in-out", *FADE_IN))
+/// })
+/// ```
+///
+/// Just like [`class!`], you can use `#![prefix = "foo"]` to give a name to the animation, for debugging purposes.
+///
+/// Just like [`class!`], the `@keyframes` rule lives forever, it is never removed from the stylesheet.
+/// So it should be created a single time (e.g. with a `static`), if it is created inside of a
+/// function then it will add a new rule every time that the function is called.
+#[macro_export]
+macro_rules! keyframes {
+    (#![prefix = $name:literal] $($methods:tt)*) => {{
+        $crate::KeyframesBuilder::__internal_done($crate::apply_methods!($crate::KeyframesBuilder::__internal_new(Some($name)), { $($methods)* }))
+    }};
+    ($($methods:tt)*) => {{
+        $crate::KeyframesBuilder::__internal_done($crate::apply_methods!($crate::KeyframesBuilder::__internal_new(None), { $($methods)* }))
+    }};
+}
+
+
+/// Adds a keyframe to a [`keyframes!`](crate::keyframes) rule.
+///
+/// The first argument is a [`Percentage`](crate::animation::Percentage), which is the offset of the keyframe.
+///
+/// The second argument is a block of method calls. Inside of the block you can use [`StylesheetBuilder`] methods:
+///
+/// ```rust
+/// keyframes! {
+///     .keyframe!(Percentage::START, {
+///         .style("transform", "translateX(0px)")
+///     })
+///
+///     .keyframe!(Percentage::END, {
+///         .style("transform", "translateX(100px)")
+///     })
+/// }
+/// ```
+///
+/// The block uses the [`apply_methods!`] macro, see the docs for [`apply_methods!`] for more details.
+#[macro_export]
+macro_rules! keyframe {
+    ($this:ident, $percentage:expr) => {
+        $crate::keyframe!($this, $percentage, {})
+    };
+    ($this:ident, $percentage:expr, { $($methods:tt)* }) => {{
+        $crate::StylesheetBuilder::__internal_done($crate::apply_methods!($crate::KeyframesBuilder::__internal_frame(&$this, $percentage), { $($methods)* }));
         $this
     }};
 }
 
 
+/// Creates a locally scoped CSS [`@font-face`](https://developer.mozilla.org/en-US/docs/Web/CSS/@font-face) rule.
+///
+/// The `font_face!` macro accepts a block of method calls. Inside of the block you can use [`FontFaceBuilder`] methods:
+///
+/// ```rust
+/// static MY_FONT: Lazy<String> = Lazy::new(|| font_face! {
+///     .src("url(/fonts/my-font.woff2) format(\"woff2\")")
+///     .font_weight("400 700")
+///     .font_display("swap")
+/// });
+/// ```
+///
+/// The block uses the [`apply_methods!`] macro, see the docs for [`apply_methods!`] for more details.
+///
+/// The `font_face!` macro returns a `String`, which is a unique font family name. You can then use
+/// that name in a [`DomBuilder`] or [`ClassBuilder`]:
+///
+/// ```rust
+/// html!("div", {
+///     .style("font-family", format!("\"{}\", sans-serif", *MY_FONT))
+/// })
+/// ```
+///
+/// Just like [`class!`], you can use `#![prefix = "foo"]` to give a name to the font family, for debugging purposes.
+#[macro_export]
+macro_rules! font_face {
+    (#![prefix = $name:literal] $($methods:tt)*) => {{
+        $crate::FontFaceBuilder::__internal_done($crate::apply_methods!($crate::FontFaceBuilder::__internal_new(Some($name)), { $($methods)* }))
+    }};
+    ($($methods:tt)*) => {{
+        $crate::FontFaceBuilder::__internal_done($crate::apply_methods!($crate::FontFaceBuilder::__internal_new(None), { $($methods)* }))
+    }};
+}
+
+
+/// Registers a locally scoped CSS custom property with [`@property`](https://developer.mozilla.org/en-US/docs/Web/CSS/@property).
+///
+/// The `custom_property!` macro accepts a block of method calls. Inside of the block you can use [`CustomPropertyBuilder`] methods:
+///
+/// ```rust
+/// static ACCENT: Lazy<String> = Lazy::new(|| custom_property! {
+///     .syntax("<color>")
+///     .inherits(true)
+///     .initial_value("rebeccapurple")
+/// });
+/// ```
+///
+/// The block uses the [`apply_methods!`] macro, see the docs for [`apply_methods!`] for more details.
+///
+/// The `custom_property!` macro returns a `String`, which is a unique custom property name (it always starts with `--`).
+/// Because the property is registered, the browser can animate and transition it:
+///
+/// ```rust
+/// html!("div", {
+///     .style(&*ACCENT, "green")
+///     .style("color", format!("var({})", *ACCENT))
+///     .style("transition", format!("{} 1s", *ACCENT))
+/// })
+/// ```
+///
+/// Just like [`class!`], you can use `#![prefix = "foo"]` to give a name to the property, for debugging purposes.
+#[macro_export]
+macro_rules! custom_property {
+    (#![prefix = $name:literal] $($methods:tt)*) => {{
+        $crate::CustomPropertyBuilder::__internal_done($crate::apply_methods!($crate::CustomPropertyBuilder::__internal_new(Some($name)), { $($methods)* }))
+    }};
+    ($($methods:tt)*) => {{
+        $crate::CustomPropertyBuilder::__internal_done($crate::apply_methods!($crate::CustomPropertyBuilder::__internal_new(None), { $($methods)* }))
+    }};
+}
+
+
 // TODO this is pretty inefficient, it iterates over the token tree one token at a time
 // TODO this should only work for ::std::clone::Clone::clone
 #[doc(hidden)]
@@ -13,6 +13,7 @@ use wasm_bindgen::UnwrapThrowExt;
 use wasm_bindgen_futures::spawn_local;
 
 use crate::bindings;
+use crate::animation::{MutableAnimation, AnimatedMapBroadcaster, Percentage, TransitionMode, Flip};
 use crate::dom::Dom;
 use crate::callbacks::Callbacks;
 
@@ -166,24 +167,211 @@ pub(crate) fn insert_child_signal<A>(element: Node, callbacks: &mut Callbacks, s
 }
 
 
+pub(crate) fn insert_child_signal_animated<A, B, F>(element: Node, callbacks: &mut Callbacks, duration: f64, mode: TransitionMode, signal: A, mut f: F)
+    where A: Signal<Item = Option<B>> + 'static,
+          F: FnMut(B, AnimatedMapBroadcaster) -> Dom + 'static {
+
+    struct Exiting {
+        id: usize,
+        child: Dom,
+        // Removes the child after the exit animation is finished
+        _future: DiscardOnDrop<CancelableFutureHandle>,
+    }
+
+    struct State {
+        element: Node,
+        marker: Node,
+        mode: TransitionMode,
+        is_inserted: bool,
+        has_value: bool,
+        exiting_id: usize,
+        child: Option<(Dom, MutableAnimation)>,
+        exiting: Vec<Exiting>,
+        // Used by TransitionMode::Sequential to wait for the exit animations to finish
+        pending: Option<(Dom, MutableAnimation)>,
+    }
+
+    impl State {
+        fn after_insert(state: Rc<RefCell<Self>>, callbacks: &mut Callbacks) {
+            callbacks.after_insert(move |_| {
+                let mut state = state.borrow_mut();
+
+                if !state.is_inserted {
+                    state.is_inserted = true;
+
+                    if let Some((ref mut child, _)) = state.child {
+                        child.callbacks.trigger_after_insert();
+                    }
+
+                    for exiting in state.exiting.iter_mut() {
+                        exiting.child.callbacks.trigger_after_insert();
+                    }
+                }
+            });
+        }
+
+        fn insert(&mut self, (mut child, animation): (Dom, MutableAnimation)) {
+            bindings::insert_child_before(&self.element, &child.element, &self.marker);
+
+            after_insert(self.is_inserted, &mut child.callbacks);
+
+            animation.animate_to(Percentage::END);
+
+            self.child = Some((child, animation));
+        }
+
+        fn exit(&mut self, state: &Rc<RefCell<Self>>) {
+            if let Some((child, animation)) = self.child.take() {
+                let id = self.exiting_id;
+                self.exiting_id += 1;
+
+                let done = animation.animate_to(Percentage::START);
+
+                let state = state.clone();
+
+                self.exiting.push(Exiting {
+                    id,
+                    child,
+                    _future: spawn_future(async move {
+                        done.await;
+
+                        let mut state = state.borrow_mut();
+                        state.remove_exiting(id);
+                    }),
+                });
+            }
+        }
+
+        fn remove_exiting(&mut self, id: usize) {
+            if let Some(index) = self.exiting.iter().position(|exiting| exiting.id == id) {
+                let exiting = self.exiting.remove(index);
+
+                bindings::remove_child(&self.element, &exiting.child.element);
+
+                exiting.child.callbacks.discard();
+            }
+
+            if self.exiting.is_empty() {
+                if let Some(pending) = self.pending.take() {
+                    self.insert(pending);
+                }
+            }
+        }
+
+        fn update(&mut self, state: &Rc<RefCell<Self>>, child: Option<(Dom, MutableAnimation)>) {
+            self.exit(state);
+
+            if let Some((old_child, _)) = self.pending.take() {
+                old_child.callbacks.discard();
+            }
+
+            if let Some(child) = child {
+                if self.mode == TransitionMode::Sequential && !self.exiting.is_empty() {
+                    self.pending = Some(child);
+
+                } else {
+                    self.insert(child);
+                }
+            }
+        }
+
+        fn on_remove(&mut self) {
+            if let Some((child, _)) = self.child.take() {
+                child.callbacks.discard();
+            }
+
+            if let Some((child, _)) = self.pending.take() {
+                child.callbacks.discard();
+            }
+
+            for exiting in self.exiting.drain(..) {
+                exiting.child.callbacks.discard();
+            }
+        }
+    }
+
+    struct OnRemove {
+        state: Rc<RefCell<State>>,
+        signal: CancelableFutureHandle,
+    }
+
+    impl Discard for OnRemove {
+        #[inline]
+        fn discard(self) {
+            self.signal.discard();
+            self.state.borrow_mut().on_remove();
+        }
+    }
+
+    // TODO replace with https://github.com/whatwg/dom/issues/736
+    let marker = bindings::create_empty_node();
+
+    bindings::append_child(&element, &marker);
+
+    let state = Rc::new(RefCell::new(State {
+        element,
+        marker,
+        mode,
+        is_inserted: false,
+        has_value: false,
+        exiting_id: 0,
+        child: None,
+        exiting: vec![],
+        pending: None,
+    }));
+
+    State::after_insert(state.clone(), callbacks);
+
+    callbacks.after_remove(OnRemove {
+        state: state.clone(),
+        signal: for_each(signal, move |value| {
+            let mut lock = state.borrow_mut();
+
+            let child = value.map(|value| {
+                // The initial child doesn't have an enter animation
+                let animation = if lock.has_value {
+                    MutableAnimation::new(duration)
+
+                } else {
+                    MutableAnimation::new_with_initial(duration, Percentage::END)
+                };
+
+                (f(value, AnimatedMapBroadcaster::new(animation.clone())), animation)
+            });
+
+            lock.has_value = true;
+
+            lock.update(&state, child);
+        }),
+    });
+}
+
+
 #[inline]
 pub(crate) fn insert_children_signal_vec<A>(element: Node, callbacks: &mut Callbacks, signal: A)
     where A: SignalVec<Item = Dom> + 'static {
+    insert_children_signal_vec_with(element, callbacks, signal, None);
+}
+
+pub(crate) fn insert_children_signal_vec_with<A>(element: Node, callbacks: &mut Callbacks, signal: A, flip: Option<Flip>)
+    where A: SignalVec<Item = Dom> + 'static {
 
     struct State {
         element: Node,
         marker: Node,
         is_inserted: bool,
         children: Vec<Dom>,
+        flip: Option<Flip>,
     }
 
     impl State {
-        fn new(element: Node, marker: Node) -> Rc<RefCell<Self>> {
+        fn new(element: Node, marker: Node, flip: Option<Flip>) -> Rc<RefCell<Self>> {
             Rc::new(RefCell::new(State {
                 element,
                 marker,
                 is_inserted: false,
                 children: vec![],
+                flip,
             }))
         }
 
@@ -209,6 +397,10 @@ pub(crate) fn insert_children_signal_vec<A>(element: Node, callbacks: &mut Callb
         }
 
         fn on_remove(&mut self) {
+            if let Some(flip) = &mut self.flip {
+                flip.stop();
+            }
+
             for dom in self.children.drain(..) {
                 dom.callbacks.discard();
             }
@@ -322,7 +514,7 @@ pub(crate) fn insert_children_signal_vec<A>(element: Node, callbacks: &mut Callb
 
     bindings::append_child(&element, &marker);
 
-    let state = State::new(element, marker);
+    let state = State::new(element, marker, flip);
 
     State::after_insert(state.clone(), callbacks);
 
@@ -330,7 +522,32 @@ pub(crate) fn insert_children_signal_vec<A>(element: Node, callbacks: &mut Callb
         state: state.clone(),
         signal: for_each_vec(signal, move |change| {
             let mut state = state.borrow_mut();
-            state.process_change(change);
+            let state = &mut *state;
+
+            match &mut state.flip {
+                // The elements can only be measured when they are in the DOM
+                Some(flip) if state.is_inserted => match change {
+                    // All of the elements are removed, so there is nothing to animate
+                    VecDiff::Clear {} => {
+                        flip.stop();
+                        state.process_change(change);
+                    },
+                    // This also handles VecDiff::Replace (e.g. when sorting with `replace_cloned`), the elements
+                    // which are in both the old and new children are matched by node identity
+                    change => {
+                        let first = flip.first(state.children.iter().map(|dom| &dom.element));
+
+                        state.process_change(change);
+
+                        if let Some(flip) = &mut state.flip {
+                            flip.play(first, state.children.iter().map(|dom| &dom.element));
+                        }
+                    },
+                },
+                _ => {
+                    state.process_change(change);
+                },
+            }
         }),
     });
 }
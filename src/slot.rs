@@ -0,0 +1,143 @@
+use futures_signals::signal::{Signal, Mutable};
+use wasm_bindgen::{JsCast, intern};
+use web_sys::{Element, HtmlSlotElement, AssignedNodesOptions};
+
+use crate::bindings;
+use crate::dom::{DomBuilder, EventOptions};
+use crate::events::SlotChange;
+use crate::utils::{on, FnDiscard};
+
+
+fn assigned_elements(slot: &HtmlSlotElement) -> Vec<Element> {
+    let options = AssignedNodesOptions::new();
+
+    // This includes the elements which are assigned to nested slots
+    options.set_flatten(true);
+
+    slot.assigned_nodes_with_options(&options)
+        .into_iter()
+        // Text nodes can also be assigned to the default slot
+        .filter_map(|node| node.dyn_into::<Element>().ok())
+        .collect()
+}
+
+
+/// Keeps track of the elements which are assigned to a `<slot>` inside of a [`shadow_root!`](crate::shadow_root).
+///
+/// Use [`DomBuilder::slot_ref`] to attach it to a `<slot>` element.
+///
+/// Cloning a `Slot` is cheap, and all of the clones refer to the same `<slot>`.
+///
+/// # Example
+///
+/// ```rust
+/// let header = Slot::new();
+///
+/// html!("div", {
+///     .shadow_root!(ShadowRootMode::Open => {
+///         .child(html!("header", {
+///             .visible_signal(header.has_assigned_signal())
+///             .child(html!("slot" => HtmlSlotElement, {
+///                 .attr("name", "header")
+///                 .slot_ref(&header)
+///             }))
+///         }))
+///
+///         // The default slot
+///         .child(html!("slot" => HtmlSlotElement, {}))
+///     })
+///
+///     .child(html!("h1", {
+///         .slot("header")
+///         .text("Title")
+///     }))
+///
+///     .child(html!("p", {
+///         .text("Content")
+///     }))
+/// })
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Slot {
+    assigned: Mutable<Vec<Element>>,
+}
+
+impl Slot {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            assigned: Mutable::new(vec![]),
+        }
+    }
+
+    /// Returns the elements which are currently assigned to the slot.
+    #[inline]
+    pub fn assigned_elements(&self) -> Vec<Element> {
+        self.assigned.get_cloned()
+    }
+
+    /// Returns a signal of the elements which are assigned to the slot.
+    ///
+    /// It is updated whenever the `slotchange` event fires.
+    #[inline]
+    pub fn assigned_elements_signal(&self) -> impl Signal<Item = Vec<Element>> {
+        self.assigned.signal_cloned()
+    }
+
+    /// Returns a signal which is `true` when at least one element is assigned to the slot.
+    #[inline]
+    pub fn has_assigned_signal(&self) -> impl Signal<Item = bool> {
+        self.assigned.signal_ref(|assigned| !assigned.is_empty())
+    }
+}
+
+
+impl<A> DomBuilder<A> where A: AsRef<HtmlSlotElement> {
+    /// Attaches a [`Slot`] to this `<slot>` element, so that the assigned elements can be observed.
+    ///
+    /// The [`Slot`] is reset to be empty when the `<slot>` element is removed.
+    #[inline]
+    pub fn slot_ref(mut self, slot: &Slot) -> Self {
+        let element: HtmlSlotElement = self.element.as_ref().clone();
+
+        {
+            let slot = slot.clone();
+            let element = element.clone();
+
+            self.callbacks.after_insert(move |_| {
+                slot.assigned.set_neq(assigned_elements(&element));
+            });
+        }
+
+        {
+            let slot = slot.clone();
+            let target = element.clone();
+
+            self.callbacks.after_remove(on(&element, &EventOptions::default(), move |_: SlotChange| {
+                slot.assigned.set_neq(assigned_elements(&target));
+            }));
+        }
+
+        {
+            let slot = slot.clone();
+
+            self.callbacks.after_remove(FnDiscard::new(move || {
+                slot.assigned.set(vec![]);
+            }));
+        }
+
+        self
+    }
+}
+
+impl<A> DomBuilder<A> where A: AsRef<Element> {
+    /// Projects this element into the named `<slot>` of its parent's shadow root.
+    ///
+    /// This sets the [`slot`](https://developer.mozilla.org/en-US/docs/Web/HTML/Global_attributes/slot) attribute.
+    #[inline]
+    #[track_caller]
+    pub fn slot(self, name: &str) -> Self {
+        bindings::set_attribute(self.element.as_ref(), intern("slot"), name);
+        self
+    }
+}
@@ -0,0 +1,83 @@
+use crate::traits::AsStr;
+
+
+/// A set of design tokens which are exposed as CSS custom properties.
+///
+/// You can use [`DomBuilder::theme`](crate::DomBuilder::theme) or
+/// [`DomBuilder::theme_signal`](crate::DomBuilder::theme_signal) to set the
+/// custom properties on an element, and then use [`var`] to refer to them
+/// in [`class!`](crate::class) or [`stylesheet!`](crate::stylesheet).
+///
+/// # Example
+///
+/// ```rust
+/// struct Colors {
+///     accent: &'static str,
+///     background: &'static str,
+/// }
+///
+/// impl Theme for Colors {
+///     fn each_var<F>(&self, mut f: F) where F: FnMut(&str, &str) {
+///         f("--accent", self.accent);
+///         f("--background", self.background);
+///     }
+/// }
+///
+/// static LIGHT: Colors = Colors { accent: "blue", background: "white" };
+/// static DARK: Colors = Colors { accent: "orange", background: "black" };
+///
+/// let is_dark = Mutable::new(false);
+///
+/// html!("div", {
+///     .theme_signal(is_dark.signal().map(|is_dark| if is_dark { &DARK } else { &LIGHT }))
+///
+///     .class(class! {
+///         .style("color", var("--accent"))
+///         .style("background-color", var("--background"))
+///     })
+/// })
+/// ```
+pub trait Theme {
+    /// Calls `f` with the name and value of every custom property.
+    ///
+    /// The names must start with `--`.
+    fn each_var<F>(&self, f: F) where F: FnMut(&str, &str);
+}
+
+impl<A> Theme for &A where A: Theme + ?Sized {
+    #[inline]
+    fn each_var<F>(&self, f: F) where F: FnMut(&str, &str) {
+        (**self).each_var(f)
+    }
+}
+
+
+/// Returns `var(name)`, which can be used to refer to a CSS custom property.
+///
+/// # Example
+///
+/// ```rust
+/// class! {
+///     .style("color", var("--accent"))
+/// }
+/// ```
+#[inline]
+pub fn var<A>(name: A) -> String where A: AsStr {
+    name.with_str(|name| format!("var({})", name))
+}
+
+/// Returns `var(name, fallback)`, which uses `fallback` if the custom property isn't set.
+///
+/// # Example
+///
+/// ```rust
+/// class! {
+///     .style("color", var_or("--accent", "blue"))
+/// }
+/// ```
+#[inline]
+pub fn var_or<A, B>(name: A, fallback: B) -> String where A: AsStr, B: AsStr {
+    name.with_str(|name| {
+        fallback.with_str(|fallback| format!("var({}, {})", name, fallback))
+    })
+}
@@ -0,0 +1,418 @@
+//! Animations which are run by the browser with the [Web Animations API](https://developer.mozilla.org/en-US/docs/Web/API/Web_Animations_API).
+//!
+//! Unlike [`MutableAnimation`](crate::animation::MutableAnimation), these animations don't update
+//! the styles from Rust on every frame, so animations of `transform` and `opacity` can run on the GPU.
+//!
+//! The durations and delays follow the [`ReducedMotion`] policy, which can be overridden with [`AnimationOptions::reduced_motion`].
+//!
+//! ```rust
+//! use dominator::web_animation::{Keyframe, AnimationOptions};
+//!
+//! html!("div", {
+//!     .animate(&[
+//!         Keyframe::new().style("opacity", "0").style("transform", "translateY(10px)"),
+//!         Keyframe::new().style("opacity", "1").style("transform", "none"),
+//!     ], &AnimationOptions {
+//!         duration: 300.0,
+//!         easing: "ease-out".to_string(),
+//!         ..AnimationOptions::default()
+//!     })
+//! })
+//! ```
+use std::future::Future;
+
+use futures_signals::signal::{Signal, SignalExt, Mutable};
+use futures_util::StreamExt;
+use js_sys::{Array, Object};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Animation, Element, FillMode, PlaybackDirection};
+
+use crate::bindings;
+use crate::animation::{ReducedMotion, reduced_motion_scale};
+use crate::dom::DomBuilder;
+use crate::operations::for_each;
+use crate::traits::{AsStr, MultiStr};
+use crate::utils::{FnDiscard, UnwrapJsExt};
+
+
+// Converts a CSS property name into the name which is used by keyframes, e.g. `background-color` into `backgroundColor`
+pub(crate) fn keyframe_property(name: &str) -> String {
+    if name.starts_with("--") {
+        name.to_string()
+
+    } else if name == "float" {
+        "cssFloat".to_string()
+
+    } else {
+        let mut output = String::with_capacity(name.len());
+        let mut upper = false;
+
+        for c in name.chars() {
+            if c == '-' {
+                upper = true;
+
+            } else if upper {
+                output.extend(c.to_uppercase());
+                upper = false;
+
+            } else {
+                output.push(c);
+            }
+        }
+
+        output
+    }
+}
+
+
+/// A single keyframe of a Web Animation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Keyframe {
+    offset: Option<f64>,
+    easing: Option<String>,
+    styles: Vec<(String, String)>,
+}
+
+impl Keyframe {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a CSS property for this keyframe, e.g. `style("background-color", "red")` or `style("width", css::Px(50.0))`.
+    ///
+    /// Keyframes don't have fallback values, so if the value has multiple choices then the first one is used.
+    #[inline]
+    pub fn style<B, C>(mut self, name: B, value: C) -> Self where B: AsStr, C: MultiStr {
+        let name = name.with_str(keyframe_property);
+
+        if let Some(value) = value.find_map(|value| Some(value.to_string())) {
+            self.styles.push((name, value));
+        }
+
+        self
+    }
+
+    /// Sets the position of this keyframe, from `0.0` to `1.0`.
+    ///
+    /// If it isn't set, the keyframes are spaced evenly.
+    #[inline]
+    pub fn offset(mut self, offset: f64) -> Self {
+        debug_assert!((0.0..=1.0).contains(&offset));
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Sets the easing which is used from this keyframe to the next keyframe.
+    #[inline]
+    pub fn easing(mut self, easing: &str) -> Self {
+        self.easing = Some(easing.to_string());
+        self
+    }
+
+    fn to_js(&self) -> Object {
+        let object = Object::new();
+
+        for (name, value) in self.styles.iter() {
+            bindings::set_property(&object, name, &JsValue::from(value));
+        }
+
+        if let Some(offset) = self.offset {
+            bindings::set_property(&object, "offset", &JsValue::from(offset));
+        }
+
+        if let Some(easing) = &self.easing {
+            bindings::set_property(&object, "easing", &JsValue::from(easing));
+        }
+
+        object
+    }
+}
+
+
+/// The timing options of a Web Animation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationOptions {
+    /// The duration in milliseconds.
+    pub duration: f64,
+    /// The delay in milliseconds before the animation starts.
+    pub delay: f64,
+    /// The delay in milliseconds after the animation ends.
+    pub end_delay: f64,
+    /// How many times the animation plays, use [`f64::INFINITY`] to play forever.
+    pub iterations: f64,
+    /// A CSS [`<easing-function>`](https://developer.mozilla.org/en-US/docs/Web/CSS/easing-function).
+    pub easing: String,
+    pub direction: PlaybackDirection,
+    pub fill: FillMode,
+    /// Overrides the global [`ReducedMotion`] policy for this animation, `None` uses the global policy.
+    pub reduced_motion: Option<ReducedMotion>,
+}
+
+impl Default for AnimationOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            duration: 0.0,
+            delay: 0.0,
+            end_delay: 0.0,
+            iterations: 1.0,
+            easing: "linear".to_string(),
+            direction: PlaybackDirection::Normal,
+            fill: FillMode::Auto,
+            reduced_motion: None,
+        }
+    }
+}
+
+impl AnimationOptions {
+    // Multiplies the durations and delays by the ReducedMotion scale
+    pub(crate) fn scaled(&self, scale: f64) -> Self {
+        Self {
+            duration: self.duration * scale,
+            delay: self.delay * scale,
+            end_delay: self.end_delay * scale,
+            ..self.clone()
+        }
+    }
+
+    // This is called when the animation starts, so that it uses the current ReducedMotion policy
+    fn to_js(&self) -> Object {
+        let options = self.scaled(reduced_motion_scale(self.reduced_motion));
+
+        let object = Object::new();
+        bindings::set_property(&object, "duration", &JsValue::from(options.duration));
+        bindings::set_property(&object, "delay", &JsValue::from(options.delay));
+        bindings::set_property(&object, "endDelay", &JsValue::from(options.end_delay));
+        bindings::set_property(&object, "iterations", &JsValue::from(self.iterations));
+        bindings::set_property(&object, "easing", &JsValue::from(&self.easing));
+        bindings::set_property(&object, "direction", &JsValue::from(self.direction));
+        bindings::set_property(&object, "fill", &JsValue::from(self.fill));
+        object
+    }
+}
+
+
+fn start(element: &Element, keyframes: &Array, options: &AnimationOptions) -> Animation {
+    bindings::animate(element, keyframes, &options.to_js())
+}
+
+fn keyframes_to_js(keyframes: &[Keyframe]) -> Array {
+    keyframes.iter().map(|keyframe| JsValue::from(keyframe.to_js())).collect()
+}
+
+
+/// A handle which is used to control a Web Animation which was started with [`DomBuilder::animate_with`]
+/// or [`DomBuilder::animate_signal_with`].
+///
+/// The methods do nothing if the animation hasn't started yet.
+///
+/// Cloning a `WebAnimation` is cheap, and all of the clones refer to the same animation.
+#[derive(Debug, Clone, Default)]
+pub struct WebAnimation {
+    animation: Mutable<Option<Animation>>,
+}
+
+impl WebAnimation {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            animation: Mutable::new(None),
+        }
+    }
+
+    /// Returns the underlying [`Animation`], which can be used for more advanced control.
+    #[inline]
+    pub fn animation(&self) -> Option<Animation> {
+        self.animation.get_cloned()
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn play(&self) {
+        if let Some(animation) = self.animation.lock_ref().as_ref() {
+            animation.play().unwrap_js();
+        }
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn pause(&self) {
+        if let Some(animation) = self.animation.lock_ref().as_ref() {
+            animation.pause().unwrap_js();
+        }
+    }
+
+    /// Reverses the direction of the animation, and plays it if it was finished.
+    #[inline]
+    #[track_caller]
+    pub fn reverse(&self) {
+        if let Some(animation) = self.animation.lock_ref().as_ref() {
+            animation.reverse().unwrap_js();
+        }
+    }
+
+    /// Jumps to the end of the animation.
+    #[inline]
+    #[track_caller]
+    pub fn finish(&self) {
+        if let Some(animation) = self.animation.lock_ref().as_ref() {
+            animation.finish().unwrap_js();
+        }
+    }
+
+    /// Stops the animation and removes its effects.
+    #[inline]
+    pub fn cancel(&self) {
+        if let Some(animation) = self.animation.lock_ref().as_ref() {
+            animation.cancel();
+        }
+    }
+
+    /// Returns a future which waits for the animation to start, and then resolves when the animation finishes.
+    ///
+    /// It resolves to `true` if the animation finished, or `false` if it was cancelled.
+    pub fn finished(&self) -> impl Future<Output = bool> {
+        let mut animations = self.animation.signal_cloned().to_stream();
+
+        async move {
+            while let Some(animation) = animations.next().await {
+                if let Some(animation) = animation {
+                    return match animation.finished() {
+                        Ok(promise) => JsFuture::from(promise).await.is_ok(),
+                        Err(_) => false,
+                    };
+                }
+            }
+
+            false
+        }
+    }
+}
+
+
+impl<A> DomBuilder<A> where A: AsRef<Element> {
+    fn start_animation(&mut self, keyframes: &[Keyframe], options: &AnimationOptions, handle: Option<WebAnimation>) {
+        let element: Element = self.element.as_ref().clone();
+        let keyframes = keyframes_to_js(keyframes);
+        let options = options.clone();
+
+        // The animation is started after the element is inserted, so that it is synchronized with the rendering
+        self.callbacks.after_insert(move |callbacks| {
+            let animation = start(&element, &keyframes, &options);
+
+            if let Some(handle) = &handle {
+                handle.animation.set(Some(animation.clone()));
+            }
+
+            callbacks.after_remove(FnDiscard::new(move || {
+                animation.cancel();
+
+                if let Some(handle) = handle {
+                    handle.animation.set(None);
+                }
+            }));
+        });
+    }
+
+    /// Plays a Web Animation after the element is inserted into the DOM.
+    ///
+    /// The animation is cancelled when the element is removed.
+    #[inline]
+    pub fn animate(mut self, keyframes: &[Keyframe], options: &AnimationOptions) -> Self {
+        self.start_animation(keyframes, options, None);
+        self
+    }
+
+    /// The same as [`animate`](DomBuilder::animate), except it can be controlled with the [`WebAnimation`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let animation = WebAnimation::new();
+    ///
+    /// html!("div", {
+    ///     .animate_with(&animation, &keyframes, &options)
+    ///     .event(clone!(animation => move |_: events::Click| {
+    ///         animation.reverse();
+    ///     }))
+    /// })
+    /// ```
+    #[inline]
+    pub fn animate_with(mut self, animation: &WebAnimation, keyframes: &[Keyframe], options: &AnimationOptions) -> Self {
+        self.start_animation(keyframes, options, Some(animation.clone()));
+        self
+    }
+
+    fn start_signal_animation<B>(&mut self, signal: B, keyframes: &[Keyframe], options: &AnimationOptions, handle: Option<WebAnimation>)
+        where B: Signal<Item = bool> + 'static {
+
+        let element: Element = self.element.as_ref().clone();
+        let keyframes = keyframes_to_js(keyframes);
+        let options = options.clone();
+
+        // The signal is only listened to after the element is inserted, for the same reason as `start_animation`
+        self.callbacks.after_insert(move |callbacks| {
+            let handle = handle.unwrap_or_default();
+
+            callbacks.after_remove(for_each(signal, {
+                let handle = handle.clone();
+
+                move |play| {
+                    if play {
+                        let animation = start(&element, &keyframes, &options);
+
+                        if let Some(old) = handle.animation.replace(Some(animation)) {
+                            old.cancel();
+                        }
+                    }
+                }
+            }));
+
+            callbacks.after_remove(FnDiscard::new(move || {
+                if let Some(animation) = handle.animation.replace(None) {
+                    animation.cancel();
+                }
+            }));
+        });
+    }
+
+    /// Plays a Web Animation every time that the signal changes to `true`.
+    ///
+    /// The signal is only used after the element is inserted into the DOM.
+    ///
+    /// If the animation is still playing, it is restarted.
+    #[inline]
+    pub fn animate_signal<B>(mut self, signal: B, keyframes: &[Keyframe], options: &AnimationOptions) -> Self
+        where B: Signal<Item = bool> + 'static {
+
+        self.start_signal_animation(signal, keyframes, options, None);
+        self
+    }
+
+    /// The same as [`animate_signal`](DomBuilder::animate_signal), except the most recent animation
+    /// can be controlled with the [`WebAnimation`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let animation = WebAnimation::new();
+    ///
+    /// html!("div", {
+    ///     .animate_signal_with(&animation, is_open.signal(), &keyframes, &options)
+    ///     .future(clone!(animation => async move {
+    ///         if animation.finished().await {
+    ///             // The first animation has finished
+    ///         }
+    ///     }))
+    /// })
+    /// ```
+    #[inline]
+    pub fn animate_signal_with<B>(mut self, animation: &WebAnimation, signal: B, keyframes: &[Keyframe], options: &AnimationOptions) -> Self
+        where B: Signal<Item = bool> + 'static {
+
+        self.start_signal_animation(signal, keyframes, options, Some(animation.clone()));
+        self
+    }
+}